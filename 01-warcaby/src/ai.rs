@@ -1,4 +1,122 @@
-use crate::{BOARD_SIZE, Checkers, Move, game::Winner};
+use std::time::{Duration, Instant};
+
+use i18n::t;
+use minimax::{BEST_EVAL, Evaluation, Evaluator, Game, Negamax, Strategy, WORST_EVAL};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::TimeControl,
+    game::{BOARD_SIZE, Checkers, Move, Turn, Winner},
+    mcts::{Mcts, MctsOptions},
+};
+
+/// How strong the AI opponent plays, i.e. how deep it searches.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    #[default]
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// Search depth used by the [`Negamax`](Engine::Negamax) AI at this difficulty.
+    /// Under a clock, it may stop at a shallower depth when the time runs out.
+    pub fn depth(self) -> u8 {
+        match self {
+            Difficulty::Easy => 2,
+            Difficulty::Medium => 4,
+            Difficulty::Hard => 6,
+        }
+    }
+
+    /// Number of rollouts per move made by the [`Mcts`](Engine::Mcts) AI at this difficulty.
+    pub fn rollouts(self) -> u32 {
+        match self {
+            Difficulty::Easy => 200,
+            Difficulty::Medium => 1000,
+            Difficulty::Hard => 5000,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => t("difficulty.easy"),
+            Difficulty::Medium => t("difficulty.medium"),
+            Difficulty::Hard => t("difficulty.hard"),
+        }
+    }
+
+    /// Returns the next harder difficulty, or the same one if it's already the hardest.
+    pub fn harder(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Medium,
+            Difficulty::Medium | Difficulty::Hard => Difficulty::Hard,
+        }
+    }
+
+    /// Returns the next easier difficulty, or the same one if it's already the easiest.
+    pub fn easier(self) -> Self {
+        match self {
+            Difficulty::Easy | Difficulty::Medium => Difficulty::Easy,
+            Difficulty::Hard => Difficulty::Medium,
+        }
+    }
+}
+
+/// Search algorithm used by the AI opponent.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Engine {
+    /// Alpha-beta search with [`CheckerEval`].
+    #[default]
+    Negamax,
+    /// Monte Carlo Tree Search, see [`crate::mcts`].
+    Mcts,
+}
+
+impl Engine {
+    pub const ALL: [Engine; 2] = [Engine::Negamax, Engine::Mcts];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Negamax => "Negamax",
+            Engine::Mcts => "MCTS",
+        }
+    }
+
+    /// Returns the other engine.
+    pub fn next(self) -> Self {
+        match self {
+            Engine::Negamax => Engine::Mcts,
+            Engine::Mcts => Engine::Negamax,
+        }
+    }
+
+    /// Creates the AI player using this engine, at the given difficulty.
+    ///
+    /// Under a time control, the player stops thinking once the time set with
+    /// [`Strategy::set_timeout`] runs out. Plain [`Negamax`] can't be stopped halfway,
+    /// so it's replaced by [`TimedNegamax`], which still searches no deeper than the difficulty allows.
+    pub fn strategy(
+        self,
+        difficulty: Difficulty,
+        time_control: TimeControl,
+    ) -> Box<dyn Strategy<CheckersRules>> {
+        match self {
+            Engine::Negamax if time_control != TimeControl::Unlimited => {
+                Box::new(TimedNegamax::new(difficulty.depth()))
+            }
+            Engine::Negamax => Box::new(Negamax::new(CheckerEval, difficulty.depth())),
+            Engine::Mcts => Box::new(Mcts::new(MctsOptions {
+                iterations: difficulty.rollouts(),
+                ..Default::default()
+            })),
+        }
+    }
+}
 
 pub struct CheckersRules;
 
@@ -53,3 +171,157 @@ impl minimax::Evaluator for CheckerEval {
             .sum()
     }
 }
+
+/// Alpha-beta search with [`CheckerEval`], which searches one level deeper at a time
+/// until it reaches its maximum depth or runs out of time.
+///
+/// [`minimax::IterativeSearch`] can't be used instead, as setting a timeout there lifts the depth limit,
+/// so under a clock the easier difficulties would play as well as the hardest one.
+pub struct TimedNegamax {
+    max_depth: u8,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    /// Best line found by the deepest completed search.
+    pv: Vec<Move>,
+}
+
+impl TimedNegamax {
+    pub fn new(max_depth: u8) -> Self {
+        TimedNegamax {
+            max_depth,
+            timeout: None,
+            deadline: None,
+            pv: Vec::new(),
+        }
+    }
+
+    /// Scores the position for the side to move, filling `line` with the best moves found from it.
+    /// Returns `None` if the time ran out before the search was done.
+    fn negamax(
+        &self,
+        state: &Checkers,
+        depth: u8,
+        mut alpha: Evaluation,
+        beta: Evaluation,
+        line: &mut Vec<Move>,
+    ) -> Option<Evaluation> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return None;
+        }
+        if let Some(winner) = CheckersRules::get_winner(state) {
+            return Some(winner.evaluate());
+        }
+        if depth == 0 {
+            return Some(CheckerEval.evaluate(state));
+        }
+
+        let mut best = WORST_EVAL;
+        let mut next_line = Vec::new();
+        for m in state.valid_moves(state.piece_for_turn()) {
+            next_line.clear();
+            let value = -self.negamax(
+                &state.apply_move(m),
+                depth - 1,
+                -beta,
+                -alpha,
+                &mut next_line,
+            )?;
+            if value > best || line.is_empty() {
+                best = value;
+                line.clear();
+                line.push(m);
+                line.append(&mut next_line);
+            }
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
+
+impl Strategy<CheckersRules> for TimedNegamax {
+    fn choose_move(&mut self, state: &Checkers) -> Option<Move> {
+        if CheckersRules::get_winner(state).is_some() {
+            return None;
+        }
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.pv.clear();
+
+        let mut moves = state.valid_moves(state.piece_for_turn());
+        moves.shuffle(&mut rand::rng());
+        let mut best_move = *moves.first()?;
+        'deepening: for depth in 1..=self.max_depth {
+            let mut best = WORST_EVAL;
+            let mut best_line = Vec::new();
+            let mut line = Vec::new();
+            for &m in &moves {
+                line.clear();
+                let Some(value) = self.negamax(
+                    &state.apply_move(m),
+                    depth - 1,
+                    WORST_EVAL,
+                    -best,
+                    &mut line,
+                ) else {
+                    break 'deepening;
+                };
+                if -value > best || best_line.is_empty() {
+                    best = -value;
+                    best_line = std::iter::once(m).chain(line.drain(..)).collect();
+                }
+            }
+
+            best_move = best_line[0];
+            self.pv = best_line;
+            // Search the best move first next time, so the deeper search can prune more.
+            if let Some(i) = moves.iter().position(|&m| m == best_move) {
+                moves[..=i].rotate_right(1);
+            }
+            if best.abs() == BEST_EVAL {
+                break;
+            }
+        }
+        Some(best_move)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    fn set_max_depth(&mut self, depth: u8) {
+        self.max_depth = depth;
+    }
+
+    fn principal_variation(&self) -> Vec<Move> {
+        self.pv.clone()
+    }
+}
+
+/// Returns whether the [`Player`](Turn::Player) can force a win in the given position,
+/// using at most `player_moves` more moves of their own.
+///
+/// This is an exhaustive search, meant for short lines (e.g. checking puzzle solutions),
+/// not for choosing moves during a regular game.
+pub fn player_wins_within(state: &Checkers, player_moves: u32) -> bool {
+    match state.get_winner() {
+        Winner::Won(winner) => return winner == Turn::Player,
+        Winner::Draw => return false,
+        Winner::InProgress => {}
+    }
+
+    let moves = state.valid_moves(state.piece_for_turn());
+    match state.turn() {
+        Turn::Player if player_moves == 0 => false,
+        Turn::Player => moves
+            .into_iter()
+            .any(|m| player_wins_within(&state.apply_move(m), player_moves - 1)),
+        Turn::Ai => moves
+            .into_iter()
+            .all(|m| player_wins_within(&state.apply_move(m), player_moves)),
+    }
+}
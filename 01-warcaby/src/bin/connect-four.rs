@@ -0,0 +1,75 @@
+//! # Connect Four
+//! A second game built on the same harness as [warcaby](https://s28840-pj.github.io/nai-71c/warcaby),
+//! showing that the AI and the terminal screens are not tied to checkers.
+
+use color_eyre::eyre::Result;
+use i18n::{Locale, t};
+use minimax::Negamax;
+use ratatui::DefaultTerminal;
+use warcaby::{
+    connect_four::{ConnectFour, ConnectFourEval, ConnectFourRules},
+    tui::{GameEnded, Match, Menu},
+};
+
+enum App {
+    PickingSides(Menu),
+    InGame(Match<ConnectFourRules>),
+    GameEnded(GameEnded),
+}
+
+impl App {
+    fn new() -> App {
+        App::PickingSides(Menu::new(
+            t("connect_four.title"),
+            [t("connect_four.first"), t("connect_four.second")],
+        ))
+    }
+
+    pub fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        loop {
+            match self {
+                App::PickingSides(menu) => {
+                    let Some(opt) = menu.run(&mut terminal)? else {
+                        return Ok(());
+                    };
+                    let ai = Negamax::new(ConnectFourEval, 6);
+                    let game = Match::new(ConnectFour::default(), opt == 0, Box::new(ai));
+                    *self = App::InGame(game);
+                }
+                App::InGame(game) => {
+                    let Some(result) = game.run(&mut terminal)? else {
+                        return Ok(());
+                    };
+                    *self = App::GameEnded(GameEnded {
+                        result,
+                        on_time: game.ended_on_time(),
+                        message: None,
+                    });
+                }
+                App::GameEnded(state) => {
+                    if !state.run(&mut terminal)? {
+                        return Ok(());
+                    }
+                    *self = App::new();
+                }
+            };
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    i18n::init(warcaby::CATALOGS, Locale::from_args(&mut args)?);
+    if !args.is_empty() {
+        eprintln!("{}", t("usage.connect_four"));
+        std::process::exit(1);
+    }
+
+    let terminal = ratatui::init();
+    let mut app = App::new();
+    app.run(terminal)?;
+    ratatui::restore();
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! Engine tournament: plays a series of checkers games between two AI engines,
+//! and prints how each of them did.
+//!
+//! ```bash
+//! cargo run --release --bin tournament -- negamax:6 mcts:2000:captures:1.4 --games 20
+//! ```
+//!
+//! Engines are given as `negamax[:depth]`, `mcts[:rollouts[:policy[:uct]]]` or `random`,
+//! where the rollout policy is one of `random`, `captures` or `eval`.
+//! With the `value-net` feature, `net[:depth[:path]]` is Negamax using the learned
+//! [`ValueNetEval`](warcaby::value_net::ValueNetEval) loaded from the given path.
+//! The engines swap colors after every game. Each game starts with a few random moves
+//! (`--opening`, 2 by default), so deterministic engines don't play the same game over and over.
+//! With `--seed N`, the openings and the MCTS rollouts are the same on every run. Negamax and
+//! `random` come from the `minimax` crate, and still break ties between equal moves at random.
+
+use std::{
+    process::exit,
+    time::{Duration, Instant},
+};
+
+use minimax::{Negamax, Random, Strategy};
+use rand::{Rng, SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+#[cfg(feature = "value-net")]
+use warcaby::value_net::ValueNetEval;
+use warcaby::{
+    ai::{CheckerEval, CheckersRules},
+    game::{Checkers, Piece, Turn, Winner},
+    mcts::{Mcts, MctsOptions},
+};
+
+fn bail_usage() -> ! {
+    eprintln!("usage: tournament [engine] [engine] [--games N] [--opening N] [--seed N]");
+    eprintln!("engines: negamax[:depth], mcts[:rollouts[:policy[:uct]]], random");
+    #[cfg(feature = "value-net")]
+    eprintln!("         net[:depth[:path]]");
+    exit(1);
+}
+
+fn unwrap_usage<T>(res: Result<T, String>) -> T {
+    match res {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            bail_usage();
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Engine {
+    Negamax {
+        depth: u8,
+    },
+    Mcts(MctsOptions),
+    Random,
+    #[cfg(feature = "value-net")]
+    Net {
+        depth: u8,
+        eval: Box<ValueNetEval>,
+    },
+}
+
+impl std::str::FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let invalid = |what: &str| format!("invalid {what} in engine {s:?}");
+
+        let engine = match parts.next() {
+            Some("negamax") => Engine::Negamax {
+                depth: match parts.next() {
+                    Some(depth) => depth.parse().map_err(|_| invalid("depth"))?,
+                    None => 6,
+                },
+            },
+            Some("mcts") => {
+                let mut options = MctsOptions::default();
+                if let Some(rollouts) = parts.next() {
+                    options.iterations = rollouts.parse().map_err(|_| invalid("rollouts"))?;
+                }
+                if let Some(policy) = parts.next() {
+                    options.rollout = policy.parse()?;
+                }
+                if let Some(uct) = parts.next() {
+                    options.exploration = uct.parse().map_err(|_| invalid("UCT constant"))?;
+                }
+                Engine::Mcts(options)
+            }
+            Some("random") => Engine::Random,
+            #[cfg(feature = "value-net")]
+            Some("net") => {
+                let depth = match parts.next() {
+                    Some(depth) => depth.parse().map_err(|_| invalid("depth"))?,
+                    None => 4,
+                };
+                let path = parts.next().unwrap_or("value-net");
+                let eval = ValueNetEval::load(path)
+                    .map_err(|e| format!("failed to load value network {path:?}: {e}"))?;
+                Engine::Net {
+                    depth,
+                    eval: Box::new(eval),
+                }
+            }
+            _ => return Err(format!("unknown engine {s:?}")),
+        };
+
+        if parts.next().is_some() {
+            return Err(format!("too many options in engine {s:?}"));
+        }
+        Ok(engine)
+    }
+}
+
+impl Engine {
+    /// Creates the player. The seed is used by the engines which can be seeded.
+    fn strategy(&self, seed: u64) -> Box<dyn Strategy<CheckersRules>> {
+        match self {
+            Engine::Negamax { depth } => Box::new(Negamax::new(CheckerEval, *depth)),
+            Engine::Mcts(options) => Box::new(Mcts::with_seed(*options, seed)),
+            Engine::Random => Box::new(Random::new()),
+            #[cfg(feature = "value-net")]
+            Engine::Net { depth, eval } => Box::new(Negamax::new((**eval).clone(), *depth)),
+        }
+    }
+}
+
+/// Results of one of the engines over the whole tournament.
+#[derive(Default)]
+struct Standing {
+    won: u32,
+    lost: u32,
+    draw: u32,
+    moves: u32,
+    thinking: Duration,
+}
+
+impl Standing {
+    fn score(&self) -> f64 {
+        self.won as f64 + self.draw as f64 / 2.
+    }
+
+    fn time_per_move(&self) -> Duration {
+        self.thinking / self.moves.max(1)
+    }
+}
+
+struct Player {
+    name: String,
+    engine: Engine,
+    standing: Standing,
+}
+
+/// Plays a single game, with `white` moving first, and random choices made with `rng`.
+/// Returns the color of the winner, or `None` for a draw.
+fn play_game(
+    white: &mut Player,
+    black: &mut Player,
+    opening: u32,
+    rng: &mut SmallRng,
+) -> (Option<Piece>, u32) {
+    // White is the side moving up the board, so it takes the place of the "player".
+    let mut state = Checkers::new(Piece::White);
+    let mut strategies = [
+        white.engine.strategy(rng.random()),
+        black.engine.strategy(rng.random()),
+    ];
+    let mut plies = 0;
+
+    loop {
+        match state.get_winner() {
+            Winner::InProgress => {}
+            Winner::Won(Turn::Player) => return (Some(Piece::White), plies),
+            Winner::Won(Turn::Ai) => return (Some(Piece::Black), plies),
+            Winner::Draw => return (None, plies),
+        }
+
+        let m = if plies < opening {
+            let moves = state.valid_moves(state.piece_for_turn());
+            *moves.choose(rng).unwrap()
+        } else {
+            let (player, strategy) = match state.turn() {
+                Turn::Player => (&mut *white, &mut strategies[0]),
+                Turn::Ai => (&mut *black, &mut strategies[1]),
+            };
+            let start = Instant::now();
+            let m = strategy
+                .choose_move(&state)
+                .expect("engine to find a move in an unfinished game");
+            player.standing.thinking += start.elapsed();
+            player.standing.moves += 1;
+            m
+        };
+
+        state = state.apply_move(m);
+        plies += 1;
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut engines = Vec::new();
+    let mut games = 10;
+    let mut opening = 2;
+    let mut seed = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                games = unwrap_usage(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid --games {value:?}")),
+                );
+            }
+            "--opening" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                opening = unwrap_usage(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid --opening {value:?}")),
+                );
+            }
+            "--seed" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                seed = Some(unwrap_usage(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| format!("invalid --seed {value:?}")),
+                ));
+            }
+            "-h" | "--help" => bail_usage(),
+            _ => engines.push(Player {
+                engine: unwrap_usage(arg.parse()),
+                name: arg,
+                standing: Standing::default(),
+            }),
+        }
+    }
+
+    let Ok([mut a, mut b]) = <[Player; 2]>::try_from(engines) else {
+        bail_usage();
+    };
+
+    let mut rng = match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_os_rng(),
+    };
+    for game in 1..=games {
+        let (white, black) = if game % 2 == 1 {
+            (&mut a, &mut b)
+        } else {
+            (&mut b, &mut a)
+        };
+
+        let (winner, plies) = play_game(white, black, opening, &mut rng);
+        let result = match winner {
+            Some(Piece::White) => {
+                white.standing.won += 1;
+                black.standing.lost += 1;
+                "white wins"
+            }
+            Some(Piece::Black) => {
+                white.standing.lost += 1;
+                black.standing.won += 1;
+                "black wins"
+            }
+            None => {
+                white.standing.draw += 1;
+                black.standing.draw += 1;
+                "draw"
+            }
+        };
+        println!(
+            "Game {game:>3}: {} (white) vs {} (black), {result} after {plies} moves",
+            white.name, black.name
+        );
+    }
+
+    println!();
+    println!(
+        "{:<30} {:>4} {:>4} {:>4} {:>7} {:>10}",
+        "Engine", "Won", "Lost", "Draw", "Score", "Time/move"
+    );
+    for player in [&a, &b] {
+        let s = &player.standing;
+        println!(
+            "{:<30} {:>4} {:>4} {:>4} {:>7} {:>9.3}s",
+            player.name,
+            s.won,
+            s.lost,
+            s.draw,
+            format!("{}/{games}", s.score()),
+            s.time_per_move().as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Trains the value network used by [`ValueNetEval`](warcaby::value_net::ValueNetEval),
+//! on positions from MCTS self-play games labeled with their outcome.
+//!
+//! ```bash
+//! cargo run --release --features value-net --bin train-value-net -- value-net --games 500
+//! ```
+//!
+//! The trained network is saved to the given path (with the `.mpk.gz` extension added),
+//! from where the tournament binary can load it as the `net` engine.
+
+use std::process::exit;
+
+use burn::{
+    backend::{Autodiff, NdArray, ndarray::NdArrayDevice},
+    data::dataloader::DataLoaderBuilder,
+    module::Module,
+    optim::AdamConfig,
+    record::CompactRecorder,
+    tensor::backend::AutodiffBackend,
+    train::{LearnerBuilder, metric::LossMetric},
+};
+use color_eyre::eyre::Result;
+use warcaby::value_net::{SampleBatcher, ValueNetConfig, self_play};
+
+type MyBackend = Autodiff<NdArray>;
+
+fn bail_usage() -> ! {
+    eprintln!(
+        "usage: train-value-net [output path] [--games N] [--rollouts N] [--opening N] [--epochs N]"
+    );
+    exit(1);
+}
+
+fn parse_usage<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    let Some(value) = value else { bail_usage() };
+    match value.parse() {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("invalid {flag} {value:?}");
+            bail_usage();
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let mut args = std::env::args().skip(1);
+    let mut output = None;
+    let mut games = 500;
+    let mut rollouts = 200;
+    let mut opening = 4;
+    let mut epochs = 10;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = parse_usage(&arg, args.next()),
+            "--rollouts" => rollouts = parse_usage(&arg, args.next()),
+            "--opening" => opening = parse_usage(&arg, args.next()),
+            "--epochs" => epochs = parse_usage(&arg, args.next()),
+            "-h" | "--help" => bail_usage(),
+            _ if output.is_none() => output = Some(arg),
+            _ => bail_usage(),
+        }
+    }
+    let Some(output) = output else { bail_usage() };
+
+    println!("Playing {games} self-play games...");
+    let dataset = self_play(games, rollouts, opening);
+    // Samples are in the order of the games, so validation uses positions from unseen games.
+    let (training_data, test_data) = dataset.split(0.9);
+
+    let device = NdArrayDevice::default();
+    let batcher_train = SampleBatcher::<MyBackend>::new();
+    let dataloader_train = DataLoaderBuilder::new(batcher_train)
+        .batch_size(64)
+        .shuffle(42)
+        .build(training_data);
+
+    let batcher_valid = SampleBatcher::<<MyBackend as AutodiffBackend>::InnerBackend>::new();
+    let dataloader_valid = DataLoaderBuilder::new(batcher_valid)
+        .batch_size(64)
+        .build(test_data);
+
+    // Checkpoints and training logs, only the final network is kept at the output path.
+    let artifact_dir = std::env::temp_dir().join("warcaby-value-net");
+    let learner = LearnerBuilder::new(&artifact_dir)
+        .metric_train_numeric(LossMetric::new())
+        .metric_valid_numeric(LossMetric::new())
+        .with_file_checkpointer(CompactRecorder::new())
+        .num_epochs(epochs)
+        .build(
+            ValueNetConfig::new().init::<MyBackend>(&device),
+            AdamConfig::new().init(),
+            1e-3,
+        );
+
+    let trained_model = learner.fit(dataloader_train, dataloader_valid).model;
+    trained_model.save_file(&output, &CompactRecorder::new())?;
+    println!("Saved the value network to {output}.mpk.gz");
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Cell, Row, Table, TableState},
+};
+
+use crate::{
+    ai::CheckersRules,
+    game::{BOARD_SIZE, Checkers, Move, Piece, Turn},
+    tui::TuiGame,
+};
+
+/// Width of the board widget, in terminal cells.
+pub const BOARD_WIDTH: u16 = BOARD_SIZE as u16 * 3;
+/// Height of the board widget, in terminal cells.
+pub const BOARD_HEIGHT: u16 = BOARD_SIZE as u16;
+
+/// Keyboard-driven cursor over the board, used to pick and move the player's pieces.
+#[derive(Default)]
+pub struct BoardCursor {
+    selected: (usize, usize),
+    moving_piece: Option<(usize, usize)>,
+    valid_moves: Vec<(usize, usize)>,
+}
+
+/// Outcome of passing a key event to the [`BoardCursor`].
+pub enum CursorAction {
+    /// The key was not handled by the cursor.
+    Ignored,
+    /// The cursor or selection changed, but no move was made.
+    Moved,
+    /// The player confirmed the given move.
+    Confirmed(Move),
+}
+
+impl BoardCursor {
+    /// Cancels any in-progress selection/move.
+    pub fn cancel(&mut self) {
+        self.moving_piece = None;
+        self.valid_moves = Vec::new();
+    }
+
+    /// Handles keyboard navigation, selection, confirmation and cancellation.
+    pub fn handle_key(&mut self, game: &Checkers, event: KeyEvent) -> CursorAction {
+        match event.code {
+            KeyCode::Char('h') | KeyCode::Left if self.selected.1 > 0 => {
+                self.selected.1 -= 1;
+            }
+            KeyCode::Char('l') | KeyCode::Right if self.selected.1 < BOARD_SIZE - 1 => {
+                self.selected.1 += 1;
+            }
+            KeyCode::Char('j') | KeyCode::Down if self.selected.0 < BOARD_SIZE - 1 => {
+                self.selected.0 += 1
+            }
+            KeyCode::Char('k') | KeyCode::Up if self.selected.0 > 0 => self.selected.0 -= 1,
+            KeyCode::Char(' ') | KeyCode::Enter
+                if game.cell(self.selected).is_some_and(|p| p == game.player()) =>
+            {
+                // Start moving: mark source and compute valid destinations for that piece.
+                self.moving_piece = Some(self.selected);
+                self.valid_moves = game
+                    .valid_moves(game.player())
+                    .iter()
+                    .filter(|m| m.from == self.selected)
+                    .map(Move::to)
+                    .collect();
+            }
+            KeyCode::Char(' ') | KeyCode::Enter
+                if self.moving_piece.is_some() && self.valid_moves.contains(&self.selected) =>
+            {
+                // Confirm move: build the Move from selected source->dest.
+                let from = self.moving_piece.unwrap();
+                let d = (
+                    self.selected.0 as isize - from.0 as isize,
+                    self.selected.1 as isize - from.1 as isize,
+                );
+
+                debug_assert_eq!(game.turn(), Turn::Player);
+                self.cancel();
+                return CursorAction::Confirmed(Move { from, d });
+            }
+            KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Esc => self.cancel(),
+            KeyCode::Char('h' | 'j' | 'k' | 'l')
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Up
+            | KeyCode::Down => {}
+            _ => return CursorAction::Ignored,
+        }
+        CursorAction::Moved
+    }
+
+    /// Draws board cells, pieces, highlights valid destinations and currently moving piece.
+    pub fn render(&self, game: &Checkers, frame: &mut Frame, area: Rect) {
+        let marked: Vec<_> = self.moving_piece.into_iter().collect();
+        let table = board_table(game, &self.valid_moves, &marked)
+            .cell_highlight_style(Style::new().bg(Color::Magenta));
+
+        let mut state = TableState::new().with_selected_cell(self.selected);
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+}
+
+impl TuiGame for CheckersRules {
+    type Input = BoardCursor;
+
+    fn handle_key(cursor: &mut BoardCursor, state: &Checkers, event: KeyEvent) -> Option<Move> {
+        match cursor.handle_key(state, event) {
+            CursorAction::Confirmed(m) => Some(m),
+            CursorAction::Moved | CursorAction::Ignored => None,
+        }
+    }
+
+    fn render(cursor: &BoardCursor, state: &Checkers, frame: &mut Frame, area: Rect) {
+        let area = centered(area, BOARD_WIDTH, BOARD_HEIGHT);
+        cursor.render(state, frame, area);
+    }
+}
+
+/// Draws a position without any cursor, e.g. for replays.
+/// If given, the source and destination of `last_move` are highlighted.
+pub fn render_position(game: &Checkers, last_move: Option<Move>, frame: &mut Frame, area: Rect) {
+    let marked: Vec<_> = last_move.iter().flat_map(|m| [m.from, m.to()]).collect();
+    frame.render_widget(board_table(game, &[], &marked), area);
+}
+
+fn board_table<'a>(
+    game: &'a Checkers,
+    valid_moves: &'a [(usize, usize)],
+    marked: &'a [(usize, usize)],
+) -> Table<'a> {
+    let rows = game.iter_rows().map(|(y, row)| {
+        Row::new(row.iter().enumerate().map(|(x, cell)| {
+            let is_dark = x & 1 == !y & 1;
+            let bg = if marked.contains(&(y, x)) {
+                // Change the board color for the marked cells, e.g. the piece selected for movement.
+                Color::Blue
+            } else if is_dark {
+                Color::Green
+            } else {
+                Color::Gray
+            };
+            let style = Style::new().bg(bg);
+            let Some(piece) = cell else {
+                let content = if valid_moves.contains(&(y, x)) {
+                    " ● "
+                } else {
+                    ""
+                };
+                return Cell::new(content).style(style);
+            };
+            let style = style.fg(match piece {
+                Piece::Black => Color::Red,
+                Piece::White => Color::White,
+            });
+            Cell::new(" ⬤ ").style(style)
+        }))
+    });
+    let widths = std::iter::repeat_n(Constraint::Length(3), BOARD_SIZE);
+
+    Table::new(rows, widths).column_spacing(0)
+}
+
+/// Returns the area of the given size, centered within `area`.
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let h = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center);
+    let v = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center);
+    let [area] = h.areas(area);
+    let [area] = v.areas(area);
+    area
+}
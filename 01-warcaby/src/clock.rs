@@ -0,0 +1,180 @@
+//! Chess clock, limiting how much time each side can spend thinking over the whole game.
+
+use std::time::{Duration, Instant};
+
+use i18n::{t, tr};
+use serde::{Deserialize, Serialize};
+
+/// Rules for how much time each side has, and how it is added back during the game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeControl {
+    /// No clock at all.
+    #[default]
+    Unlimited,
+    /// Each side has a fixed amount of time for the whole game.
+    SuddenDeath { base: Duration },
+    /// After every move, `increment` is added to the clock of the side which moved.
+    Fischer { base: Duration, increment: Duration },
+    /// After every move, the time spent on it is given back, but no more than `delay`.
+    /// Unlike [`Fischer`](TimeControl::Fischer), quick moves don't build up extra time.
+    Bronstein { base: Duration, delay: Duration },
+}
+
+const fn minutes(n: u64) -> Duration {
+    Duration::from_secs(n * 60)
+}
+
+impl TimeControl {
+    /// Time controls to pick from in the menu.
+    pub const PRESETS: [TimeControl; 5] = [
+        TimeControl::Unlimited,
+        TimeControl::SuddenDeath { base: minutes(5) },
+        TimeControl::SuddenDeath { base: minutes(1) },
+        TimeControl::Fischer {
+            base: minutes(3),
+            increment: Duration::from_secs(2),
+        },
+        TimeControl::Bronstein {
+            base: minutes(3),
+            delay: Duration::from_secs(2),
+        },
+    ];
+
+    pub fn name(self) -> String {
+        let min = |d: Duration| d.as_secs() / 60;
+        match self {
+            TimeControl::Unlimited => t("time_control.unlimited").to_string(),
+            TimeControl::SuddenDeath { base } => tr!("time_control.sudden_death", base = min(base)),
+            TimeControl::Fischer { base, increment } => tr!(
+                "time_control.fischer",
+                base = min(base),
+                increment = increment.as_secs()
+            ),
+            TimeControl::Bronstein { base, delay } => tr!(
+                "time_control.bronstein",
+                base = min(base),
+                delay = delay.as_secs()
+            ),
+        }
+    }
+
+    /// Returns the next preset after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::PRESETS.iter().position(|&tc| tc == self);
+        Self::PRESETS[index.map_or(0, |index| (index + 1) % Self::PRESETS.len())]
+    }
+
+    fn base(self) -> Option<Duration> {
+        match self {
+            TimeControl::Unlimited => None,
+            TimeControl::SuddenDeath { base }
+            | TimeControl::Fischer { base, .. }
+            | TimeControl::Bronstein { base, .. } => Some(base),
+        }
+    }
+}
+
+/// One of the two sides of a game between the human player and the AI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Human,
+    Ai,
+}
+
+impl Side {
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Human => Side::Ai,
+            Side::Ai => Side::Human,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Side::Human => 0,
+            Side::Ai => 1,
+        }
+    }
+}
+
+/// How many more moves the AI assumes it has to make, when splitting its remaining time.
+const EXPECTED_MOVES_LEFT: u32 = 30;
+
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    to_move: Side,
+    /// When the side to move started thinking, `None` until the clock is started.
+    turn_started: Option<Instant>,
+}
+
+impl Clock {
+    /// Creates a stopped clock, with `first` to move.
+    /// Returns `None` for [`TimeControl::Unlimited`].
+    pub fn new(control: TimeControl, first: Side) -> Option<Self> {
+        let base = control.base()?;
+        Some(Clock {
+            control,
+            remaining: [base; 2],
+            to_move: first,
+            turn_started: None,
+        })
+    }
+
+    /// Starts counting down the time of the side to move, unless the clock is already running.
+    pub fn start(&mut self) {
+        self.turn_started.get_or_insert_with(Instant::now);
+    }
+
+    pub fn to_move(&self) -> Side {
+        self.to_move
+    }
+
+    fn spent(&self) -> Duration {
+        self.turn_started
+            .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    /// Returns the time left for the given side, including the time spent on the current move.
+    pub fn remaining(&self, side: Side) -> Duration {
+        let remaining = self.remaining[side.index()];
+        if side == self.to_move {
+            remaining.saturating_sub(self.spent())
+        } else {
+            remaining
+        }
+    }
+
+    /// Returns the side whose time ran out, if any.
+    pub fn flag_fallen(&self) -> Option<Side> {
+        (self.remaining(self.to_move) == Duration::ZERO).then_some(self.to_move)
+    }
+
+    /// Ends the move of the side to move, and starts the clock of the other side.
+    pub fn press(&mut self) {
+        let spent = self.spent();
+        let remaining = &mut self.remaining[self.to_move.index()];
+        *remaining = remaining.saturating_sub(spent);
+        match self.control {
+            TimeControl::Unlimited | TimeControl::SuddenDeath { .. } => {}
+            TimeControl::Fischer { increment, .. } => *remaining += increment,
+            TimeControl::Bronstein { delay, .. } => *remaining += spent.min(delay),
+        }
+
+        self.to_move = self.to_move.opposite();
+        self.turn_started = Some(Instant::now());
+    }
+
+    /// Returns how long the AI should think about its current move,
+    /// so that its remaining time lasts for the rest of the game.
+    pub fn ai_budget(&self) -> Duration {
+        let remaining = self.remaining(Side::Ai);
+        // Time which will be given back after the move can be spent on it, too.
+        let bonus = match self.control {
+            TimeControl::Unlimited | TimeControl::SuddenDeath { .. } => Duration::ZERO,
+            TimeControl::Fischer { increment, .. } => increment,
+            TimeControl::Bronstein { delay, .. } => delay,
+        };
+        (remaining / EXPECTED_MOVES_LEFT + bonus).min(remaining / 2)
+    }
+}
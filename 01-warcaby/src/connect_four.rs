@@ -0,0 +1,252 @@
+//! Connect Four, played with the same AI and terminal harness as checkers.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Style},
+    widgets::{Cell, Row, Table},
+};
+
+use crate::{board::centered, tui::TuiGame};
+
+pub const COLUMNS: usize = 7;
+pub const ROWS: usize = 6;
+
+/// How many discs in a line are needed to win.
+const LINE: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Disc {
+    Red,
+    Yellow,
+}
+
+impl Disc {
+    pub fn opposite(self) -> Self {
+        match self {
+            Disc::Red => Disc::Yellow,
+            Disc::Yellow => Disc::Red,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ConnectFour {
+    /// Rows of the board, with row 0 at the top.
+    board: [[Option<Disc>; COLUMNS]; ROWS],
+    to_move: Disc,
+    last_drop: Option<(usize, usize)>,
+}
+
+impl Default for ConnectFour {
+    fn default() -> Self {
+        ConnectFour {
+            board: [[None; COLUMNS]; ROWS],
+            to_move: Disc::Red,
+            last_drop: None,
+        }
+    }
+}
+
+impl ConnectFour {
+    /// Returns the color of the disc which will be dropped next.
+    pub fn to_move(&self) -> Disc {
+        self.to_move
+    }
+
+    /// Returns whether another disc fits in the given column.
+    pub fn can_drop(&self, column: usize) -> bool {
+        column < COLUMNS && self.board[0][column].is_none()
+    }
+
+    /// Returns a copy of the board, after dropping a disc into the given column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column is full.
+    pub fn drop_disc(&self, column: usize) -> Self {
+        let mut board = self.board;
+        let row = (0..ROWS)
+            .rev()
+            .find(|&row| board[row][column].is_none())
+            .expect("column to have space left");
+        board[row][column] = Some(self.to_move);
+
+        ConnectFour {
+            board,
+            to_move: self.to_move.opposite(),
+            last_drop: Some((row, column)),
+        }
+    }
+
+    /// Returns whether the last dropped disc completed a line.
+    fn last_drop_won(&self) -> bool {
+        let Some((row, column)) = self.last_drop else {
+            return false;
+        };
+        let disc = self.board[row][column];
+
+        // Count discs of the same color in both directions of each line through the last drop.
+        let count = |dy: isize, dx: isize| {
+            (1..LINE)
+                .map_while(|i| {
+                    let y = row
+                        .checked_add_signed(dy * i as isize)
+                        .filter(|&y| y < ROWS)?;
+                    let x = column
+                        .checked_add_signed(dx * i as isize)
+                        .filter(|&x| x < COLUMNS)?;
+                    (self.board[y][x] == disc).then_some(())
+                })
+                .count()
+        };
+
+        [(0, 1), (1, 0), (1, 1), (1, -1)]
+            .into_iter()
+            .any(|(dy, dx)| 1 + count(dy, dx) + count(-dy, -dx) >= LINE)
+    }
+
+    /// Returns an iterator over all possible lines of [`LINE`] cells on the board.
+    fn windows(&self) -> impl Iterator<Item = [Option<Disc>; LINE]> + '_ {
+        let directions = [(0, 1), (1, 0), (1, 1), (1, -1)];
+        (0..ROWS)
+            .flat_map(|y| (0..COLUMNS).map(move |x| (y, x)))
+            .flat_map(move |start| directions.map(|d| (start, d)))
+            .filter_map(|((y, x), (dy, dx)): ((usize, usize), (isize, isize))| {
+                let end_y = y.checked_add_signed(dy * (LINE as isize - 1))?;
+                let end_x = x.checked_add_signed(dx * (LINE as isize - 1))?;
+                if end_y >= ROWS || end_x >= COLUMNS {
+                    return None;
+                }
+                Some(std::array::from_fn(|i| {
+                    let y = y.checked_add_signed(dy * i as isize).unwrap();
+                    let x = x.checked_add_signed(dx * i as isize).unwrap();
+                    self.board[y][x]
+                }))
+            })
+    }
+}
+
+pub struct ConnectFourRules;
+
+impl minimax::Game for ConnectFourRules {
+    type S = ConnectFour;
+    /// Index of the column the disc is dropped into.
+    type M = usize;
+
+    fn generate_moves(state: &Self::S, moves: &mut Vec<Self::M>) {
+        moves.extend((0..COLUMNS).filter(|&column| state.can_drop(column)));
+    }
+
+    fn apply(state: &mut Self::S, m: Self::M) -> Option<Self::S> {
+        Some(state.drop_disc(m))
+    }
+
+    fn get_winner(state: &Self::S) -> Option<minimax::Winner> {
+        if state.last_drop_won() {
+            Some(minimax::Winner::PlayerJustMoved)
+        } else if (0..COLUMNS).all(|column| !state.can_drop(column)) {
+            Some(minimax::Winner::Draw)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct ConnectFourEval;
+
+impl minimax::Evaluator for ConnectFourEval {
+    type G = ConnectFourRules;
+
+    /// Every line of four cells, which is not blocked by the opponent,
+    /// is worth more the more discs it already has.
+    /// Lines of the player to move are added, while lines of the opponent are subtracted.
+    fn evaluate(&self, s: &<Self::G as minimax::Game>::S) -> minimax::Evaluation {
+        const LINE_VALUE: [i16; LINE] = [0, 1, 4, 16];
+
+        s.windows()
+            .map(|window| {
+                let mine = window.iter().filter(|&&c| c == Some(s.to_move)).count();
+                let theirs = window
+                    .iter()
+                    .filter(|&&c| c == Some(s.to_move.opposite()))
+                    .count();
+                match (mine, theirs) {
+                    (mine, 0) => LINE_VALUE[mine.min(LINE - 1)],
+                    (0, theirs) => -LINE_VALUE[theirs.min(LINE - 1)],
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+}
+
+/// Column above which the human player's next disc hangs.
+pub struct ColumnCursor {
+    column: usize,
+}
+
+impl Default for ColumnCursor {
+    fn default() -> Self {
+        ColumnCursor {
+            column: COLUMNS / 2,
+        }
+    }
+}
+
+impl TuiGame for ConnectFourRules {
+    type Input = ColumnCursor;
+
+    fn handle_key(
+        cursor: &mut ColumnCursor,
+        state: &ConnectFour,
+        event: KeyEvent,
+    ) -> Option<usize> {
+        match event.code {
+            KeyCode::Char('h') | KeyCode::Left => cursor.column = cursor.column.saturating_sub(1),
+            KeyCode::Char('l') | KeyCode::Right => {
+                cursor.column = (cursor.column + 1).min(COLUMNS - 1)
+            }
+            KeyCode::Char(' ' | 'j') | KeyCode::Enter | KeyCode::Down
+                if state.can_drop(cursor.column) =>
+            {
+                return Some(cursor.column);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(cursor: &ColumnCursor, state: &ConnectFour, frame: &mut Frame, area: Rect) {
+        let disc_style = |disc: Disc| {
+            Style::new().fg(match disc {
+                Disc::Red => Color::Red,
+                Disc::Yellow => Color::Yellow,
+            })
+        };
+
+        // The disc waiting to be dropped hangs above the board.
+        let hanging = Row::new((0..COLUMNS).map(|x| {
+            if x == cursor.column {
+                Cell::new(" ⬤ ").style(disc_style(state.to_move))
+            } else {
+                Cell::new("")
+            }
+        }));
+        let rows = state.board.iter().map(|row| {
+            Row::new(row.iter().map(|cell| {
+                let style = Style::new().bg(Color::Blue);
+                match cell {
+                    Some(disc) => Cell::new(" ⬤ ").style(disc_style(*disc).bg(Color::Blue)),
+                    None => Cell::new(" ◯ ").style(style.fg(Color::Black)),
+                }
+            }))
+        });
+        let widths = std::iter::repeat_n(Constraint::Length(3), COLUMNS);
+        let table = Table::new(std::iter::once(hanging).chain(rows), widths).column_spacing(0);
+
+        let area = centered(area, COLUMNS as u16 * 3, ROWS as u16 + 1);
+        frame.render_widget(table, area);
+    }
+}
@@ -0,0 +1,369 @@
+//! Board diagrams as images, e.g. for teaching material.
+//!
+//! A position can be exported as SVG or PNG, with the board coordinates around it
+//! and optionally an arrow showing a move. A whole game can be exported as an animated GIF.
+//! Same as in the terminal, the player's pieces are at the bottom of the board.
+
+use std::{fmt::Write, fs::File, io::BufWriter, path::Path};
+
+use color_eyre::eyre::{Result, bail};
+use image::{
+    Delay, Frame, Rgba, RgbaImage,
+    codecs::gif::{GifEncoder, Repeat},
+};
+
+use crate::game::{BOARD_SIZE, Checkers, Move, Piece, cell_name};
+
+/// Size of a single board cell, in pixels.
+const CELL: u32 = 64;
+/// Space around the board, for the coordinates.
+const MARGIN: u32 = 28;
+/// Width and height of the whole image.
+const SIZE: u32 = BOARD_SIZE as u32 * CELL + 2 * MARGIN;
+
+const PIECE_RADIUS: f32 = CELL as f32 * 0.38;
+const ARROW_WIDTH: f32 = 8.;
+const ARROW_HEAD_LENGTH: f32 = 24.;
+const ARROW_HEAD_WIDTH: f32 = 30.;
+const ARROW_OPACITY: f32 = 0.8;
+
+const BACKGROUND: [u8; 3] = [0xfa, 0xfa, 0xfa];
+const LIGHT_CELL: [u8; 3] = [0xf0, 0xd9, 0xb5];
+const DARK_CELL: [u8; 3] = [0xb5, 0x88, 0x63];
+const WHITE_PIECE: [u8; 3] = [0xf8, 0xf8, 0xf8];
+const BLACK_PIECE: [u8; 3] = [0x20, 0x20, 0x20];
+const OUTLINE: [u8; 3] = [0x10, 0x10, 0x10];
+const ARROW: [u8; 3] = [0xe6, 0x78, 0x14];
+const TEXT: [u8; 3] = [0x30, 0x30, 0x30];
+
+/// How long each move is shown in an exported GIF.
+const GIF_MOVE_DELAY_MS: u32 = 800;
+/// How long the final position is shown, before the animation starts over.
+const GIF_END_DELAY_MS: u32 = 3000;
+
+type Point = (f32, f32);
+
+fn cell_center((y, x): (usize, usize)) -> Point {
+    let center = |i: usize| (MARGIN + i as u32 * CELL + CELL / 2) as f32;
+    (center(x), center(y))
+}
+
+fn is_dark(y: usize, x: usize) -> bool {
+    x & 1 == !y & 1
+}
+
+fn piece_color(piece: Piece) -> [u8; 3] {
+    match piece {
+        Piece::White => WHITE_PIECE,
+        Piece::Black => BLACK_PIECE,
+    }
+}
+
+/// Coordinate labels, with the point their text is centered on.
+/// Columns are labeled below the board, rows to the left of it.
+fn labels() -> impl Iterator<Item = (char, Point)> {
+    let below = (MARGIN + BOARD_SIZE as u32 * CELL + MARGIN / 2) as f32;
+    let left = (MARGIN / 2) as f32;
+    (0..BOARD_SIZE).flat_map(move |i| {
+        // The cell name of e.g. `(7, 0)` is `a1`, so both labels can be taken from it.
+        let name: Vec<char> = cell_name((BOARD_SIZE - 1 - i, i)).chars().collect();
+        let (center_x, _) = cell_center((0, i));
+        let (_, center_y) = cell_center((BOARD_SIZE - 1 - i, 0));
+        [(name[0], (center_x, below)), (name[1], (left, center_y))]
+    })
+}
+
+/// Outline of the arrow for a move: the ends of its shaft, and the corners of its head.
+struct ArrowShape {
+    shaft: [Point; 2],
+    head: [Point; 3],
+}
+
+impl ArrowShape {
+    /// Returns `None` for a move ending where it starts, as there is no direction to point the arrow in.
+    fn new(m: Move) -> Option<Self> {
+        let from = cell_center(m.from);
+        let to = cell_center(m.to());
+        let len = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+        if len == 0. {
+            return None;
+        }
+        let dir = ((to.0 - from.0) / len, (to.1 - from.1) / len);
+        let normal = (-dir.1, dir.0);
+
+        let base = (
+            to.0 - dir.0 * ARROW_HEAD_LENGTH,
+            to.1 - dir.1 * ARROW_HEAD_LENGTH,
+        );
+        let half = ARROW_HEAD_WIDTH / 2.;
+        Some(ArrowShape {
+            shaft: [from, base],
+            head: [
+                to,
+                (base.0 + normal.0 * half, base.1 + normal.1 * half),
+                (base.0 - normal.0 * half, base.1 - normal.1 * half),
+            ],
+        })
+    }
+}
+
+fn rgb([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Draws the position as an SVG image, with an arrow for the given move.
+pub fn svg(state: &Checkers, arrow: Option<Move>) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" viewBox="0 0 {SIZE} {SIZE}">"#
+    )
+    .ok();
+    writeln!(
+        svg,
+        r#"<rect width="{SIZE}" height="{SIZE}" fill="{}"/>"#,
+        rgb(BACKGROUND)
+    )
+    .ok();
+
+    for (y, x, _) in state.iter_board() {
+        let color = if is_dark(y, x) { DARK_CELL } else { LIGHT_CELL };
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="{}"/>"#,
+            MARGIN + x as u32 * CELL,
+            MARGIN + y as u32 * CELL,
+            rgb(color)
+        )
+        .ok();
+    }
+
+    for (y, x, piece) in state.iter_pieces() {
+        let (cx, cy) = cell_center((y, x));
+        writeln!(
+            svg,
+            r#"<circle cx="{cx}" cy="{cy}" r="{PIECE_RADIUS}" fill="{}" stroke="{}" stroke-width="2"/>"#,
+            rgb(piece_color(piece)),
+            rgb(OUTLINE)
+        )
+        .ok();
+    }
+
+    if let Some(ArrowShape { shaft, head }) = arrow.and_then(ArrowShape::new) {
+        let color = rgb(ARROW);
+        writeln!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{color}" stroke-width="{ARROW_WIDTH}" stroke-opacity="{ARROW_OPACITY}"/>"#,
+            shaft[0].0, shaft[0].1, shaft[1].0, shaft[1].1
+        )
+        .ok();
+        let points: Vec<_> = head.iter().map(|(x, y)| format!("{x},{y}")).collect();
+        writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{color}" fill-opacity="{ARROW_OPACITY}"/>"#,
+            points.join(" ")
+        )
+        .ok();
+    }
+
+    for (label, (x, y)) in labels() {
+        writeln!(
+            svg,
+            r#"<text x="{x}" y="{y}" fill="{}" font-family="sans-serif" font-size="16" text-anchor="middle" dominant-baseline="central">{label}</text>"#,
+            rgb(TEXT)
+        )
+        .ok();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Mixes the color into the pixel, with the given opacity.
+fn blend(image: &mut RgbaImage, x: u32, y: u32, color: [u8; 3], alpha: f32) {
+    let pixel = image.get_pixel_mut(x, y);
+    for (channel, value) in pixel.0.iter_mut().zip(color) {
+        *channel = (*channel as f32 * (1. - alpha) + value as f32 * alpha).round() as u8;
+    }
+}
+
+/// Fills a shape given by its signed distance function (negative inside the shape),
+/// checking only the pixels within `min..max`. Edges are smoothed over a single pixel.
+fn fill(
+    image: &mut RgbaImage,
+    (min, max): (Point, Point),
+    color: [u8; 3],
+    opacity: f32,
+    distance: impl Fn(Point) -> f32,
+) {
+    let range = |lo: f32, hi: f32| (lo.floor().max(0.) as u32)..(hi.ceil().min(SIZE as f32) as u32);
+    for y in range(min.1 - 1., max.1 + 1.) {
+        for x in range(min.0 - 1., max.0 + 1.) {
+            let coverage = (0.5 - distance((x as f32 + 0.5, y as f32 + 0.5))).clamp(0., 1.);
+            if coverage > 0. {
+                blend(image, x, y, color, coverage * opacity);
+            }
+        }
+    }
+}
+
+fn fill_disc(image: &mut RgbaImage, (cx, cy): Point, r: f32, color: [u8; 3]) {
+    let bounds = ((cx - r, cy - r), (cx + r, cy + r));
+    fill(image, bounds, color, 1., |(x, y)| {
+        ((x - cx).powi(2) + (y - cy).powi(2)).sqrt() - r
+    });
+}
+
+fn fill_arrow(image: &mut RgbaImage, ArrowShape { shaft, head }: ArrowShape) {
+    let [a, b] = shaft;
+    let half_width = ARROW_WIDTH / 2.;
+
+    // Distance from the shaft, i.e. from the segment between its ends.
+    let shaft_distance = |p: Point| {
+        let ab = (b.0 - a.0, b.1 - a.1);
+        let ap = (p.0 - a.0, p.1 - a.1);
+        let t = ((ap.0 * ab.0 + ap.1 * ab.1) / (ab.0 * ab.0 + ab.1 * ab.1)).clamp(0., 1.);
+        let closest = (a.0 + ab.0 * t, a.1 + ab.1 * t);
+        ((p.0 - closest.0).powi(2) + (p.1 - closest.1).powi(2)).sqrt() - half_width
+    };
+
+    // Distance from the head, i.e. the largest distance outside of any of its edges.
+    // The inside of the triangle is on the same side of each edge, given by the orientation of its corners.
+    let orientation = {
+        let [a, b, c] = head;
+        ((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)).signum()
+    };
+    let head_distance = |p: Point| {
+        (0..3)
+            .map(|i| {
+                let (a, b) = (head[i], head[(i + 1) % 3]);
+                let len = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+                -orientation * ((b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)) / len
+            })
+            .fold(f32::MIN, f32::max)
+    };
+
+    // Both parts are filled at once, so the translucent arrow isn't darker where they overlap.
+    let points = [a, b, head[0], head[1], head[2]];
+    let min = |f: fn(&Point) -> f32| points.iter().map(f).fold(f32::MAX, f32::min) - half_width;
+    let max = |f: fn(&Point) -> f32| points.iter().map(f).fold(f32::MIN, f32::max) + half_width;
+    let bounds = ((min(|p| p.0), min(|p| p.1)), (max(|p| p.0), max(|p| p.1)));
+    fill(image, bounds, ARROW, ARROW_OPACITY, |p| {
+        shaft_distance(p).min(head_distance(p))
+    });
+}
+
+/// Glyphs of the coordinate labels, 5 pixels wide and 7 high.
+/// Each row is a bit mask, with the leftmost pixel in the highest bit.
+#[rustfmt::skip]
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        'a' => [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111],
+        'b' => [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110],
+        'c' => [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110],
+        'd' => [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111],
+        'e' => [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110],
+        'f' => [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000],
+        'g' => [0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110],
+        'h' => [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        _ => [0; 7],
+    }
+}
+
+/// Draws a label character, scaled up twice, centered on the given point.
+fn draw_label(image: &mut RgbaImage, c: char, (cx, cy): Point) {
+    const SCALE: u32 = 2;
+    let left = cx as u32 - 5 * SCALE / 2;
+    let top = cy as u32 - 7 * SCALE / 2;
+    for (row, bits) in glyph(c).into_iter().enumerate() {
+        for column in 0..5 {
+            if bits & (0b10000 >> column) == 0 {
+                continue;
+            }
+            for dy in 0..SCALE {
+                for dx in 0..SCALE {
+                    let x = left + column * SCALE + dx;
+                    let y = top + row as u32 * SCALE + dy;
+                    blend(image, x, y, TEXT, 1.);
+                }
+            }
+        }
+    }
+}
+
+/// Draws the position as a raster image, with an arrow for the given move.
+pub fn render(state: &Checkers, arrow: Option<Move>) -> RgbaImage {
+    let [r, g, b] = BACKGROUND;
+    let mut image = RgbaImage::from_pixel(SIZE, SIZE, Rgba([r, g, b, 0xff]));
+
+    for (y, x, _) in state.iter_board() {
+        let color = if is_dark(y, x) { DARK_CELL } else { LIGHT_CELL };
+        for py in 0..CELL {
+            for px in 0..CELL {
+                let px = MARGIN + x as u32 * CELL + px;
+                let py = MARGIN + y as u32 * CELL + py;
+                blend(&mut image, px, py, color, 1.);
+            }
+        }
+    }
+
+    for (y, x, piece) in state.iter_pieces() {
+        let center = cell_center((y, x));
+        fill_disc(&mut image, center, PIECE_RADIUS + 1., OUTLINE);
+        fill_disc(&mut image, center, PIECE_RADIUS - 1., piece_color(piece));
+    }
+
+    if let Some(shape) = arrow.and_then(ArrowShape::new) {
+        fill_arrow(&mut image, shape);
+    }
+
+    for (label, center) in labels() {
+        draw_label(&mut image, label, center);
+    }
+
+    image
+}
+
+/// Saves a diagram of the position, as SVG or PNG depending on the file extension.
+pub fn save(state: &Checkers, arrow: Option<Move>, path: &Path) -> Result<()> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => std::fs::write(path, svg(state, arrow))?,
+        Some("png") => render(state, arrow).save(path)?,
+        _ => bail!("unsupported image format of {path:?}, expected .svg or .png"),
+    }
+    Ok(())
+}
+
+/// Saves an animated GIF of a game, one frame for each move, with the move marked by an arrow.
+pub fn save_gif(start: &Checkers, moves: &[Move], path: &Path) -> Result<()> {
+    let mut frames = Vec::with_capacity(moves.len() + 1);
+    let mut state = start.clone();
+    frames.push(render(&state, None));
+    for &m in moves {
+        state = state.apply_move(m);
+        frames.push(render(&state, Some(m)));
+    }
+
+    let last = frames.len() - 1;
+    let frames = frames.into_iter().enumerate().map(|(i, image)| {
+        let delay = if i == last {
+            GIF_END_DELAY_MS
+        } else {
+            GIF_MOVE_DELAY_MS
+        };
+        Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(delay, 1))
+    });
+
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames)?;
+    Ok(())
+}
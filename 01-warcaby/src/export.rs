@@ -0,0 +1,111 @@
+//! The `export` subcommand, saving diagrams of positions and recorded games without starting the game.
+
+use std::{path::Path, process::exit};
+
+use color_eyre::eyre::{Result, bail, eyre};
+use i18n::{t, tr};
+use warcaby::{
+    diagram,
+    game::{Checkers, Move, Piece},
+};
+
+use crate::stats::GameHistory;
+
+/// Catalog keys of the usage lines, and of the notes printed below them.
+const USAGE: [&str; 2] = ["usage.export.game", "usage.export.diagram"];
+const USAGE_NOTES: [&str; 3] = [
+    "usage.export.numbering",
+    "usage.export.formats",
+    "usage.export.diagram_file",
+];
+
+fn bail_usage() -> ! {
+    for key in USAGE {
+        eprintln!("{}", t(key));
+    }
+    eprintln!();
+    for key in USAGE_NOTES {
+        eprintln!("{}", t(key));
+    }
+    exit(1);
+}
+
+/// Runs the subcommand, with the arguments following `export`.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    match args.next().as_deref() {
+        Some("game") => export_game(args),
+        Some("diagram") => export_diagram(args),
+        _ => bail_usage(),
+    }
+}
+
+fn export_game(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let (Some(number), Some(output)) = (args.next(), args.next()) else {
+        bail_usage();
+    };
+    let mut position = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--move" => position = Some(args.next().unwrap_or_else(|| bail_usage()).parse()?),
+            _ => bail_usage(),
+        }
+    }
+
+    let history = GameHistory::load()?;
+    let games = history.games();
+    let number: usize = number.parse()?;
+    let game = number
+        .checked_sub(1)
+        .and_then(|i| games.iter().rev().nth(i))
+        .ok_or_else(|| eyre!(tr!("usage.no_game", number = number, count = games.len())))?;
+
+    let start = Checkers::new(game.color);
+    let output = Path::new(&output);
+    if output.extension().is_some_and(|ext| ext == "gif") {
+        diagram::save_gif(&start, &game.moves, output)?;
+    } else {
+        let moves = &game.moves[..position.unwrap_or(game.moves.len()).min(game.moves.len())];
+        let state = moves.iter().fold(start, |state, &m| state.apply_move(m));
+        diagram::save(&state, moves.last().copied(), output)?;
+    }
+    Ok(())
+}
+
+fn export_diagram(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let (Some(input), Some(output)) = (args.next(), args.next()) else {
+        bail_usage();
+    };
+    let mut player = Piece::White;
+    let mut arrow = None;
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| bail_usage());
+        match arg.as_str() {
+            "--player" => {
+                player = match value.as_str() {
+                    "white" => Piece::White,
+                    "black" => Piece::Black,
+                    _ => bail_usage(),
+                }
+            }
+            "--arrow" => arrow = Some(value.parse::<Move>().map_err(|e| eyre!(e))?),
+            _ => bail_usage(),
+        }
+    }
+
+    let text = std::fs::read_to_string(&input)?;
+    let state = Checkers::from_diagram(player, &text)
+        .ok_or_else(|| eyre!(tr!("usage.invalid_diagram", input = format!("{input:?}"))))?;
+    // The arrow may show a move of either side, but it has to be one they can make.
+    if let Some(m) = arrow
+        && ![Piece::White, Piece::Black]
+            .into_iter()
+            .any(|piece| state.valid_moves(piece).contains(&m))
+    {
+        bail!(tr!(
+            "usage.invalid_move",
+            m = m,
+            input = format!("{input:?}")
+        ));
+    }
+    diagram::save(&state, arrow, Path::new(&output))
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 pub const BOARD_SIZE: usize = 8;
 
 #[derive(Clone)]
@@ -7,13 +9,14 @@ pub struct Checkers {
     turn: Turn,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub struct Move {
     pub from: (usize, usize),
     pub d: (isize, isize),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Piece {
     Black,
     White,
@@ -32,6 +35,76 @@ pub enum Turn {
     Ai,
 }
 
+impl Move {
+    /// Returns the cell the piece lands on after this move.
+    pub fn to(&self) -> (usize, usize) {
+        (
+            self.from.0.checked_add_signed(self.d.0).unwrap(),
+            self.from.1.checked_add_signed(self.d.1).unwrap(),
+        )
+    }
+
+    /// Returns whether this move jumps over (and captures) an opponent's piece.
+    pub fn is_capture(&self) -> bool {
+        self.d.0.abs() > 1
+    }
+}
+
+/// Returns the name of a cell in the usual notation, e.g. `c3`.
+/// Columns are lettered from the left, rows are numbered from the bottom of the board.
+pub fn cell_name((y, x): (usize, usize)) -> String {
+    format!("{}{}", (b'a' + x as u8) as char, BOARD_SIZE - y)
+}
+
+/// Parses a cell name in the notation used by [`cell_name`].
+pub fn parse_cell(name: &str) -> Option<(usize, usize)> {
+    let mut chars = name.chars();
+    let column = chars.next()?;
+    let row: usize = chars.as_str().parse().ok()?;
+    let x = (column as usize).checked_sub('a' as usize)?;
+    let y = BOARD_SIZE.checked_sub(row)?;
+    (x < BOARD_SIZE && y < BOARD_SIZE).then_some((y, x))
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sep = if self.is_capture() { 'x' } else { '-' };
+        write!(f, "{}{sep}{}", cell_name(self.from), cell_name(self.to()))
+    }
+}
+
+impl std::str::FromStr for Move {
+    type Err = String;
+
+    /// Parses a move written as `c3-d4` (or `c3xe5` for captures).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once(['-', 'x'])
+            .ok_or_else(|| format!("invalid move {s:?}"))?;
+        let from = parse_cell(from).ok_or_else(|| format!("invalid cell {from:?}"))?;
+        let to = parse_cell(to).ok_or_else(|| format!("invalid cell {to:?}"))?;
+        let d = (
+            to.0 as isize - from.0 as isize,
+            to.1 as isize - from.1 as isize,
+        );
+        Ok(Move { from, d })
+    }
+}
+
+impl From<Move> for String {
+    fn from(m: Move) -> Self {
+        m.to_string()
+    }
+}
+
+impl TryFrom<String> for Move {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 impl Piece {
     pub fn opposite(self) -> Self {
         match self {
@@ -96,6 +169,44 @@ impl Checkers {
         }
     }
 
+    /// Create a game board from a text diagram, with the [`Player`](Turn::Player) to move.
+    ///
+    /// The diagram consists of [`BOARD_SIZE`] lines, each with [`BOARD_SIZE`] characters,
+    /// listed from the top row down. `w` marks a white piece, `b` a black piece,
+    /// and any other character an empty cell.
+    /// Same as in [`Checkers::new`], the pieces of the player always move up the board.
+    pub fn from_diagram(player: Piece, diagram: &str) -> Option<Self> {
+        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
+        let mut rows = diagram
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+
+        for row in board.iter_mut() {
+            let line = rows.next()?;
+            if line.chars().count() != BOARD_SIZE {
+                return None;
+            }
+            for (cell, c) in row.iter_mut().zip(line.chars()) {
+                *cell = match c {
+                    'w' => Some(Piece::White),
+                    'b' => Some(Piece::Black),
+                    _ => None,
+                };
+            }
+        }
+
+        if rows.next().is_some() {
+            return None;
+        }
+
+        Some(Checkers {
+            board,
+            player,
+            turn: Turn::Player,
+        })
+    }
+
     /// Returns who'll move next.
     pub fn turn(&self) -> Turn {
         self.turn
@@ -205,7 +316,7 @@ impl Checkers {
         let mut board = self.board;
 
         // If the move is a jump (distance > 1), remove the jumped piece.
-        if m.d.0.abs() > 1 {
+        if m.is_capture() {
             // Get the coordinates of the jumped piece.
             let d = (m.d.0 / 2, m.d.1 / 2);
             let y = m.from.0.checked_add_signed(d.0).unwrap();
@@ -0,0 +1,18 @@
+#![doc = include_str!("../README.md")]
+
+pub mod ai;
+pub mod board;
+pub mod clock;
+pub mod connect_four;
+pub mod diagram;
+pub mod game;
+pub mod mcts;
+pub mod tui;
+#[cfg(feature = "value-net")]
+pub mod value_net;
+
+/// Message catalogs of the user interface, to pass to [`i18n::init`].
+pub const CATALOGS: i18n::Catalogs = i18n::Catalogs {
+    pl: include_str!("../locales/pl.txt"),
+    en: include_str!("../locales/en.txt"),
+};
@@ -0,0 +1,257 @@
+//! Monte Carlo Tree Search player for checkers.
+//!
+//! Instead of scoring positions with a fixed formula, MCTS plays many quick games ("rollouts")
+//! from the current position, and picks the move which did best in them.
+//! The search tree grows towards the most promising lines, with the UCT formula balancing
+//! between exploiting moves which won so far, and exploring the ones which were rarely tried.
+
+use std::time::{Duration, Instant};
+
+use minimax::{Evaluator, Strategy};
+use rand::{Rng, SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::{CheckerEval, CheckersRules},
+    game::{Checkers, Move, Turn, Winner},
+};
+
+/// How moves are picked while playing out a game from a leaf of the search tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Rollout {
+    /// Any valid move, picked uniformly at random.
+    Random,
+    /// A random capture if there is one, otherwise any random move.
+    #[default]
+    Captures,
+    /// The move rated best by [`CheckerEval`], with some random moves mixed in.
+    EvalGuided,
+}
+
+impl Rollout {
+    pub const ALL: [Rollout; 3] = [Rollout::Random, Rollout::Captures, Rollout::EvalGuided];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rollout::Random => "random",
+            Rollout::Captures => "captures",
+            Rollout::EvalGuided => "eval",
+        }
+    }
+}
+
+impl std::str::FromStr for Rollout {
+    type Err = String;
+
+    /// Parses a rollout policy by its [`name`](Rollout::name).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rollout::ALL
+            .into_iter()
+            .find(|rollout| rollout.name() == s)
+            .ok_or_else(|| format!("unknown rollout policy {s:?}"))
+    }
+}
+
+/// Chance of an eval-guided rollout playing a random move instead of the best rated one.
+/// Without it, every rollout from the same position would play out exactly the same.
+const EVAL_GUIDED_EXPLORATION: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MctsOptions {
+    /// Number of rollouts made before choosing a move, unless the timeout runs out first.
+    pub iterations: u32,
+    /// Exploration constant of the UCT formula.
+    /// Higher values spread the search wider, lower ones focus it on the best moves so far.
+    pub exploration: f64,
+    pub rollout: Rollout,
+}
+
+impl Default for MctsOptions {
+    fn default() -> Self {
+        MctsOptions {
+            iterations: 2000,
+            exploration: std::f64::consts::SQRT_2,
+            rollout: Rollout::default(),
+        }
+    }
+}
+
+pub struct Mcts {
+    options: MctsOptions,
+    timeout: Option<Duration>,
+    rng: SmallRng,
+}
+
+/// A position in the search tree.
+struct Node {
+    /// Move leading to this node from its parent, `None` for the root.
+    m: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Valid moves which don't have a child node yet.
+    untried: Vec<Move>,
+    /// Side which made the move leading to this node. Results are counted from its point of view.
+    mover: Turn,
+    visits: u32,
+    /// Sum of rollout results, 1 for each win and 0.5 for each draw.
+    score: f64,
+}
+
+impl Node {
+    fn new(m: Option<Move>, parent: Option<usize>, state: &Checkers) -> Self {
+        let untried = match state.get_winner() {
+            Winner::InProgress => state.valid_moves(state.piece_for_turn()),
+            Winner::Won(_) | Winner::Draw => Vec::new(),
+        };
+        Node {
+            m,
+            parent,
+            children: Vec::new(),
+            untried,
+            mover: state.turn().opposite(),
+            visits: 0,
+            score: 0.,
+        }
+    }
+}
+
+impl Mcts {
+    pub fn new(options: MctsOptions) -> Self {
+        Self::with_rng(options, SmallRng::from_os_rng())
+    }
+
+    /// Creates a player making the same choices every time, e.g. for reproducible tournaments.
+    pub fn with_seed(options: MctsOptions, seed: u64) -> Self {
+        Self::with_rng(options, SmallRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(options: MctsOptions, rng: SmallRng) -> Self {
+        Mcts {
+            options,
+            timeout: None,
+            rng,
+        }
+    }
+
+    /// Runs a single round of selection, expansion, rollout and backpropagation.
+    fn iterate(&mut self, tree: &mut Vec<Node>, root: &Checkers) {
+        let mut node = 0;
+        let mut state = root.clone();
+
+        // Walk down the fully expanded part of the tree, picking the best child by UCT.
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            node = self.select_child(tree, node);
+            state = state.apply_move(tree[node].m.unwrap());
+        }
+
+        // Add one of the untried moves to the tree.
+        if !tree[node].untried.is_empty() {
+            let index = self.rng.random_range(0..tree[node].untried.len());
+            let m = tree[node].untried.swap_remove(index);
+            state = state.apply_move(m);
+
+            tree.push(Node::new(Some(m), Some(node), &state));
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+
+        let winner = self.rollout(state);
+
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut tree[index];
+            node.visits += 1;
+            node.score += match winner {
+                Winner::Won(turn) if turn == node.mover => 1.,
+                Winner::Won(_) => 0.,
+                Winner::Draw | Winner::InProgress => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    fn select_child(&self, tree: &[Node], node: usize) -> usize {
+        let ln_visits = (tree[node].visits as f64).ln();
+        let uct = |child: usize| {
+            let child = &tree[child];
+            let visits = child.visits as f64;
+            child.score / visits + self.options.exploration * (ln_visits / visits).sqrt()
+        };
+
+        tree[node]
+            .children
+            .iter()
+            .copied()
+            .max_by(|&a, &b| uct(a).total_cmp(&uct(b)))
+            .unwrap()
+    }
+
+    /// Plays the game out until the end, using the configured rollout policy.
+    /// Pieces can only move forward, so every game ends after a limited number of moves.
+    fn rollout(&mut self, mut state: Checkers) -> Winner {
+        loop {
+            let winner = state.get_winner();
+            if winner != Winner::InProgress {
+                return winner;
+            }
+
+            let moves = state.valid_moves(state.piece_for_turn());
+            let m = self.rollout_move(&state, &moves);
+            state = state.apply_move(m);
+        }
+    }
+
+    fn rollout_move(&mut self, state: &Checkers, moves: &[Move]) -> Move {
+        match self.options.rollout {
+            Rollout::Random => *moves.choose(&mut self.rng).unwrap(),
+            Rollout::Captures => {
+                let captures: Vec<_> = moves.iter().filter(|m| m.is_capture()).collect();
+                match captures.choose(&mut self.rng) {
+                    Some(&&m) => m,
+                    None => *moves.choose(&mut self.rng).unwrap(),
+                }
+            }
+            Rollout::EvalGuided if self.rng.random_bool(EVAL_GUIDED_EXPLORATION) => {
+                *moves.choose(&mut self.rng).unwrap()
+            }
+            Rollout::EvalGuided => {
+                // The evaluation is from the point of view of the side to move,
+                // which after our move is the opponent.
+                *moves
+                    .iter()
+                    .min_by_key(|&&m| CheckerEval.evaluate(&state.apply_move(m)))
+                    .unwrap()
+            }
+        }
+    }
+}
+
+impl Strategy<CheckersRules> for Mcts {
+    fn choose_move(&mut self, state: &Checkers) -> Option<Move> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let mut tree = vec![Node::new(None, None, state)];
+        if tree[0].untried.is_empty() {
+            return None;
+        }
+
+        for _ in 0..self.options.iterations.max(1) {
+            self.iterate(&mut tree, state);
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+        }
+
+        // The most visited move is the most reliable one, as its score is based on the most rollouts.
+        tree[0]
+            .children
+            .iter()
+            .map(|&child| &tree[child])
+            .max_by_key(|child| child.visits)
+            .and_then(|child| child.m)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{Result, WrapErr, bail, eyre};
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use i18n::{t, tr};
+use minimax::{Game, Negamax, Strategy};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    text::{Line, Text},
+    widgets::{List, ListState},
+};
+use serde::{Deserialize, Serialize};
+
+use warcaby::{
+    ai::{CheckerEval, CheckersRules, player_wins_within},
+    board::{BOARD_HEIGHT, BOARD_WIDTH, BoardCursor, CursorAction, centered},
+    game::{Checkers, Move, Piece, Turn, Winner},
+    tui::EventResult,
+};
+
+use crate::{AI, storage};
+
+/// Puzzles bundled with the game, used unless `WARCABY_PUZZLES` points to another file.
+const BUILTIN_PUZZLES: &str = include_str!("../puzzles.txt");
+
+/// A position with a known winning line for the [`Player`](Turn::Player).
+pub struct Puzzle {
+    pub name: String,
+    pub start: Checkers,
+    /// Moves of the winning line, alternating between the player and the AI,
+    /// starting with the player.
+    pub solution: Vec<Move>,
+}
+
+impl Puzzle {
+    /// Returns how many moves the player needs to make to solve the puzzle.
+    pub fn player_moves(&self) -> u32 {
+        self.solution.len().div_ceil(2) as u32
+    }
+
+    /// Parses a collection of puzzles.
+    ///
+    /// Puzzles are separated by empty lines. Each puzzle starts with `key: value` headers
+    /// (`name`, `player` and `solution`), followed by the board diagram
+    /// in the format accepted by [`Checkers::from_diagram`]. Lines starting with `#` are ignored.
+    pub fn parse_all(text: &str) -> Result<Vec<Puzzle>> {
+        let mut puzzles = Vec::new();
+        let mut block = Vec::new();
+
+        let lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .chain([""]);
+        for line in lines {
+            if !line.is_empty() {
+                block.push(line);
+            } else if !block.is_empty() {
+                puzzles.push(Puzzle::parse(&block)?);
+                block.clear();
+            }
+        }
+
+        Ok(puzzles)
+    }
+
+    fn parse(lines: &[&str]) -> Result<Puzzle> {
+        let mut name = None;
+        let mut player = None;
+        let mut solution = None;
+        let mut diagram = String::new();
+
+        for line in lines {
+            let Some((key, value)) = line.split_once(':') else {
+                diagram.push_str(line);
+                diagram.push('\n');
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "player" => {
+                    player = Some(match value {
+                        "white" => Piece::White,
+                        "black" => Piece::Black,
+                        _ => bail!("unknown player color {value:?}"),
+                    })
+                }
+                "solution" => {
+                    let moves: Result<Vec<Move>, _> =
+                        value.split_whitespace().map(str::parse).collect();
+                    solution = Some(moves.map_err(|e| eyre!(e))?);
+                }
+                _ => bail!("unknown puzzle header {key:?}"),
+            }
+        }
+
+        let name = name.ok_or_else(|| eyre!("puzzle without a name"))?;
+        let player = player.ok_or_else(|| eyre!("puzzle {name:?} has no player color"))?;
+        let solution = solution
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| eyre!("puzzle {name:?} has no solution"))?;
+        let start = Checkers::from_diagram(player, &diagram)
+            .ok_or_else(|| eyre!("puzzle {name:?} has an invalid board diagram"))?;
+
+        // A mistyped move would otherwise only show up once a player gets to it.
+        let mut state = start.clone();
+        for (i, &m) in solution.iter().enumerate() {
+            let mut moves = Vec::new();
+            CheckersRules::generate_moves(&state, &mut moves);
+            if !moves.contains(&m) {
+                bail!(
+                    "move {} of the solution of puzzle {name:?} ({m}) is not valid",
+                    i + 1
+                );
+            }
+            state = state.apply_move(m);
+        }
+        if state.get_winner() != Winner::Won(Turn::Player) {
+            bail!("puzzle {name:?} solution does not win");
+        }
+
+        Ok(Puzzle {
+            name,
+            start,
+            solution,
+        })
+    }
+
+    /// Loads the puzzle collection, either from the file given in `WARCABY_PUZZLES`,
+    /// or the one bundled with the game.
+    pub fn load_all() -> Result<Vec<Puzzle>> {
+        match std::env::var_os("WARCABY_PUZZLES") {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("failed to read puzzles from {path:?}"))?;
+                Puzzle::parse_all(&text)
+            }
+            None => Puzzle::parse_all(BUILTIN_PUZZLES),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize)]
+pub struct PuzzleRecord {
+    pub solved: u32,
+    pub failed: u32,
+}
+
+/// Results of solved and failed puzzles, kept between sessions.
+#[derive(Default, Serialize, Deserialize)]
+pub struct PuzzleStats {
+    records: HashMap<String, PuzzleRecord>,
+}
+
+impl PuzzleStats {
+    const FILE: &str = "puzzles.json";
+
+    /// Loads the statistics saved by the previous sessions.
+    pub fn load() -> Result<Self> {
+        storage::load(Self::FILE)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        storage::save(Self::FILE, self)
+    }
+
+    pub fn get(&self, puzzle: &str) -> PuzzleRecord {
+        self.records.get(puzzle).copied().unwrap_or_default()
+    }
+
+    fn record(&mut self, puzzle: &str, solved: bool) {
+        let record = self.records.entry(puzzle.to_string()).or_default();
+        if solved {
+            record.solved += 1;
+        } else {
+            record.failed += 1;
+        }
+    }
+}
+
+/// Puzzle collection together with the player's results, passed between the puzzle screens.
+#[derive(Default)]
+pub struct PuzzleSet {
+    puzzles: Vec<Puzzle>,
+    stats: PuzzleStats,
+}
+
+impl PuzzleSet {
+    pub fn load() -> Result<Self> {
+        Ok(PuzzleSet {
+            puzzles: Puzzle::load_all()?,
+            stats: PuzzleStats::load()?,
+        })
+    }
+}
+
+pub struct PuzzleSelect {
+    set: PuzzleSet,
+    state: ListState,
+}
+
+impl PuzzleSelect {
+    pub fn new(set: PuzzleSet) -> Self {
+        PuzzleSelect {
+            set,
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<SolvingPuzzle>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            match event::read()? {
+                Event::Key(e) if e.is_press() => match self.handle_key(e) {
+                    EventResult::Continue => {}
+                    EventResult::Quit => return Ok(None),
+                    EventResult::End(index) => {
+                        let set = std::mem::take(&mut self.set);
+                        return Ok(Some(SolvingPuzzle::new(set, index)));
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> EventResult<usize> {
+        match event.code {
+            KeyCode::Char('q') => return EventResult::Quit,
+            KeyCode::Char('j') | KeyCode::Down => self.state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.state.select_previous(),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(index) = self.state.selected()
+                    && index < self.set.puzzles.len()
+                {
+                    return EventResult::End(index);
+                }
+            }
+            _ => {}
+        }
+        EventResult::Continue
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let v = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [label, rest] = v.areas(frame.area());
+
+        let items = self.set.puzzles.iter().map(|puzzle| {
+            let record = self.set.stats.get(&puzzle.name);
+            let mark = if record.solved > 0 { "✔" } else { " " };
+            tr!(
+                "puzzle.entry",
+                mark = mark,
+                name = puzzle.name,
+                moves = puzzle.player_moves(),
+                solved = record.solved,
+                failed = record.failed,
+            )
+        });
+        let list = List::new(items).highlight_style(Style::new().bg(Color::Blue));
+
+        frame.render_widget(Text::from(t("puzzle.pick")).centered(), label);
+        frame.render_stateful_widget(list, rest, &mut self.state);
+    }
+}
+
+enum PuzzleStatus {
+    Solving,
+    Solved,
+    Failed { hint: Option<Move> },
+}
+
+pub struct SolvingPuzzle {
+    set: PuzzleSet,
+    index: usize,
+    game: Checkers,
+    cursor: BoardCursor,
+    /// How many moves of the solution have been played so far.
+    played: usize,
+    /// Whether all the moves so far followed the stored solution.
+    on_book: bool,
+    status: PuzzleStatus,
+    /// Error from saving the results, shown below the status.
+    message: Option<String>,
+    ai: AI,
+}
+
+impl SolvingPuzzle {
+    fn new(set: PuzzleSet, index: usize) -> Self {
+        let game = set.puzzles[index].start.clone();
+        SolvingPuzzle {
+            set,
+            index,
+            game,
+            cursor: BoardCursor::default(),
+            played: 0,
+            on_book: true,
+            status: PuzzleStatus::Solving,
+            message: None,
+            ai: Negamax::new(CheckerEval, 6),
+        }
+    }
+
+    fn puzzle(&self) -> &Puzzle {
+        &self.set.puzzles[self.index]
+    }
+
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<PuzzleSelect>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            match event::read()? {
+                Event::Key(e) if e.is_press() => match self.handle_key(e) {
+                    EventResult::Continue => {}
+                    EventResult::Quit => return Ok(None),
+                    EventResult::End(()) => {
+                        let set = std::mem::take(&mut self.set);
+                        return Ok(Some(PuzzleSelect::new(set)));
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> EventResult<()> {
+        if !matches!(self.status, PuzzleStatus::Solving) {
+            // Any key after the puzzle is over goes back to the puzzle list.
+            return EventResult::End(());
+        }
+
+        if event.code == KeyCode::Char('q') {
+            return EventResult::Quit;
+        }
+
+        if let CursorAction::Confirmed(m) = self.cursor.handle_key(&self.game, event) {
+            self.player_move(m);
+        }
+        EventResult::Continue
+    }
+
+    fn player_move(&mut self, m: Move) {
+        let after = self.game.apply_move(m);
+        let moves_left = self
+            .puzzle()
+            .player_moves()
+            .saturating_sub(self.played as u32 / 2);
+
+        // A move is correct if it follows the stored solution,
+        // or if the engine confirms it still wins in the remaining number of moves.
+        let expected = self.expected_move();
+        let correct =
+            expected == Some(m) || player_wins_within(&after, moves_left.saturating_sub(1));
+        if !correct {
+            self.finish(PuzzleStatus::Failed { hint: expected });
+            return;
+        }
+
+        self.on_book &= expected == Some(m);
+        self.game = after;
+        self.played += 1;
+
+        if self.game.get_winner() == Winner::Won(Turn::Player) {
+            self.finish(PuzzleStatus::Solved);
+            return;
+        }
+
+        // Reply with the stored solution while possible, and with the engine afterwards.
+        let reply = self
+            .expected_move()
+            .or_else(|| self.ai.choose_move(&self.game));
+        if let Some(reply) = reply {
+            self.game = self.game.apply_move(reply);
+            self.played += 1;
+        }
+
+        if self.game.get_winner() != Winner::InProgress || moves_left <= 1 {
+            // Should not happen for a correct line, but don't leave the player stuck.
+            self.finish(PuzzleStatus::Failed { hint: None });
+        }
+    }
+
+    /// Returns the next move of the stored solution, if the game still follows it.
+    fn expected_move(&self) -> Option<Move> {
+        if self.on_book {
+            self.puzzle().solution.get(self.played).copied()
+        } else {
+            None
+        }
+    }
+
+    /// Ends the puzzle, and saves the result. Results which couldn't be saved
+    /// are still counted until the game is closed.
+    fn finish(&mut self, status: PuzzleStatus) {
+        let solved = matches!(status, PuzzleStatus::Solved);
+        let name = self.puzzle().name.clone();
+        self.set.stats.record(&name, solved);
+        if let Err(e) = self.set.stats.save() {
+            self.message = Some(tr!("puzzle.save_failed", error = e));
+        }
+        self.status = status;
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let puzzle = self.puzzle();
+        let color = match puzzle.start.player() {
+            Piece::White => t("color.white"),
+            Piece::Black => t("color.black"),
+        };
+        let status = match self.status {
+            PuzzleStatus::Solving => {
+                tr!(
+                    "puzzle.to_move",
+                    color = color,
+                    moves = puzzle.player_moves()
+                )
+            }
+            PuzzleStatus::Solved => t("puzzle.solved").to_string(),
+            PuzzleStatus::Failed { hint: Some(hint) } => tr!("puzzle.failed_hint", hint = hint),
+            PuzzleStatus::Failed { hint: None } => t("puzzle.failed").to_string(),
+        };
+        let text = Text::from(vec![
+            Line::from(puzzle.name.as_str()),
+            Line::from(status),
+            Line::from(self.message.clone().unwrap_or_default()),
+        ])
+        .centered();
+
+        let area = centered(frame.area(), frame.area().width, BOARD_HEIGHT + 3);
+        let v =
+            Layout::vertical([Constraint::Length(BOARD_HEIGHT), Constraint::Length(3)]).spacing(1);
+        let [board, info] = v.areas(area);
+        let board = centered(board, BOARD_WIDTH, BOARD_HEIGHT);
+
+        self.cursor.render(&self.game, frame, board);
+        frame.render_widget(text, info);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIAGRAM: &str = "\
+........
+......b.
+...b.w..
+b.w.....
+.b...w..
+........
+........
+..w.....";
+
+    fn puzzle(solution: &str) -> String {
+        format!("name: Open door\nplayer: white\nsolution: {solution}\n{DIAGRAM}\n")
+    }
+
+    #[test]
+    fn builtin_puzzles_parse() {
+        assert!(!Puzzle::parse_all(BUILTIN_PUZZLES).unwrap().is_empty());
+    }
+
+    #[test]
+    fn solution_has_to_win() {
+        assert!(Puzzle::parse_all(&puzzle("f6xh8")).is_ok());
+
+        let error = Puzzle::parse_all(&puzzle("c5-b6")).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "puzzle \"Open door\" solution does not win"
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use std::{path::Path, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use i18n::{t, tr};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout},
+    style::{Color, Style},
+    text::{Line, Text},
+    widgets::{List, ListState, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+
+use warcaby::{
+    ai::{Difficulty, Engine},
+    board::{BOARD_HEIGHT, BOARD_WIDTH, centered, render_position},
+    diagram,
+    game::{Checkers, Move, Piece},
+    tui::{EventResult, GameResult},
+};
+
+use crate::{App, PickingSides, Settings, storage};
+
+/// A single finished game against the AI.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub date: DateTime<Utc>,
+    pub color: Piece,
+    pub difficulty: Difficulty,
+    /// Games recorded before the engine could be picked were all played against Negamax.
+    #[serde(default)]
+    pub engine: Engine,
+    pub result: GameResult,
+    /// All moves of the game, both the player's and the AI's, in the order they were made.
+    pub moves: Vec<Move>,
+    pub duration: Duration,
+}
+
+impl GameRecord {
+    fn summary_line(&self) -> String {
+        let secs = self.duration.as_secs();
+        // Translated names have different lengths, so the columns fit the longest one.
+        let color_width = width([Piece::White, Piece::Black].map(color_name));
+        let difficulty_width = width(Difficulty::ALL.map(Difficulty::name));
+        let engine_width = width(Engine::ALL.map(Engine::name));
+        let result_width =
+            width([GameResult::Won, GameResult::Lost, GameResult::Draw].map(GameResult::name));
+        let moves_width = width([1, 2, 5].map(|n| tr!("stats.moves"; n)));
+        format!(
+            "{date}  {color:<color_width$}  {difficulty:<difficulty_width$}  {engine:<engine_width$}  {result:<result_width$}  {moves:>moves_width$}  {min}:{sec:02}",
+            date = self.date.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            color = color_name(self.color),
+            difficulty = self.difficulty.name(),
+            engine = self.engine.name(),
+            result = self.result.name(),
+            moves = tr!("stats.moves"; self.moves.len()),
+            min = secs / 60,
+            sec = secs % 60,
+        )
+    }
+}
+
+fn color_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::White => t("color.white"),
+        Piece::Black => t("color.black"),
+    }
+}
+
+/// Returns the length of the longest of the names, in characters.
+fn width<S: AsRef<str>>(names: impl IntoIterator<Item = S>) -> usize {
+    names
+        .into_iter()
+        .map(|name| name.as_ref().chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// All finished games, kept between sessions.
+#[derive(Default, Serialize, Deserialize)]
+pub struct GameHistory {
+    games: Vec<GameRecord>,
+}
+
+impl GameHistory {
+    const FILE: &str = "history.json";
+
+    /// Loads the games saved by the previous sessions.
+    pub fn load() -> Result<Self> {
+        storage::load(Self::FILE)
+    }
+
+    /// Returns all recorded games, from the oldest one.
+    pub fn games(&self) -> &[GameRecord] {
+        &self.games
+    }
+
+    /// Adds a finished game to the history saved on disk.
+    pub fn record(game: GameRecord) -> Result<()> {
+        let mut history = Self::load()?;
+        history.games.push(game);
+        storage::save(Self::FILE, &history)
+    }
+
+    /// Returns the current streak, i.e. the result of the last game
+    /// and how many games in a row ended the same way.
+    fn current_streak(&self) -> Option<(GameResult, usize)> {
+        let last = self.games.last()?.result;
+        let count = self
+            .games
+            .iter()
+            .rev()
+            .take_while(|game| game.result == last)
+            .count();
+        Some((last, count))
+    }
+
+    /// Returns the longest series of games won in a row.
+    fn best_win_streak(&self) -> usize {
+        self.games
+            .split(|game| game.result != GameResult::Won)
+            .map(<[_]>::len)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    won: usize,
+    lost: usize,
+    draw: usize,
+}
+
+impl Summary {
+    fn of<'a>(games: impl Iterator<Item = &'a GameRecord>) -> Self {
+        let mut summary = Summary::default();
+        for game in games {
+            match game.result {
+                GameResult::Won => summary.won += 1,
+                GameResult::Lost => summary.lost += 1,
+                GameResult::Draw => summary.draw += 1,
+            }
+        }
+        summary
+    }
+
+    fn row(&self, label: &'static str) -> Row<'static> {
+        let played = self.won + self.lost + self.draw;
+        let win_rate = if played == 0 {
+            "-".to_string()
+        } else {
+            format!("{:.0}%", self.won as f64 / played as f64 * 100.)
+        };
+        Row::new([
+            label.to_string(),
+            played.to_string(),
+            self.won.to_string(),
+            self.lost.to_string(),
+            self.draw.to_string(),
+            win_rate,
+        ])
+    }
+}
+
+pub struct Statistics {
+    history: GameHistory,
+    state: ListState,
+    /// Settings picked in the menu, restored when going back to it.
+    settings: Settings,
+}
+
+impl Statistics {
+    pub fn new(settings: Settings) -> Result<Self> {
+        Ok(Self::with_history(GameHistory::load()?, settings))
+    }
+
+    fn with_history(history: GameHistory, settings: Settings) -> Self {
+        Statistics {
+            history,
+            state: ListState::default().with_selected(Some(0)),
+            settings,
+        }
+    }
+
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<App>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            match event::read()? {
+                Event::Key(e) if e.is_press() => match self.handle_key(e) {
+                    EventResult::Continue => {}
+                    EventResult::Quit => return Ok(None),
+                    EventResult::End(state) => return Ok(Some(state)),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> EventResult<App> {
+        match event.code {
+            KeyCode::Char('q') => return EventResult::Quit,
+            KeyCode::Esc => {
+                return EventResult::End(App::PickingSides(PickingSides {
+                    settings: self.settings,
+                    ..Default::default()
+                }));
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.state.select_previous(),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(selected) = self.state.selected()
+                    && selected < self.history.games.len()
+                {
+                    // The list shows the most recent games first.
+                    let index = self.history.games.len() - 1 - selected;
+                    let history = std::mem::take(&mut self.history);
+                    let replay = Replay::new(history, index, self.settings);
+                    return EventResult::End(App::Replay(replay));
+                }
+            }
+            _ => {}
+        }
+        EventResult::Continue
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let games = &self.history.games;
+        let v = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length((Difficulty::ALL.len() + Engine::ALL.len()) as u16 + 4),
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ]);
+        let [title, summary, streaks, history_title, history, help] = v.areas(frame.area());
+
+        let header = Row::new([
+            "",
+            t("stats.played"),
+            t("stats.won"),
+            t("stats.lost"),
+            t("stats.draw"),
+            t("stats.win_rate"),
+        ])
+        .style(Style::new().fg(Color::Yellow));
+        let rows = Difficulty::ALL
+            .iter()
+            .map(|&difficulty| {
+                Summary::of(games.iter().filter(|g| g.difficulty == difficulty))
+                    .row(difficulty.name())
+            })
+            .chain(Engine::ALL.map(|engine| {
+                Summary::of(games.iter().filter(|g| g.engine == engine)).row(engine.name())
+            }))
+            .chain([Piece::White, Piece::Black].map(|color| {
+                Summary::of(games.iter().filter(|g| g.color == color)).row(color_name(color))
+            }))
+            .chain([Summary::of(games.iter()).row(t("stats.total"))]);
+        let table = Table::new(rows, [Constraint::Length(8); 6]).header(header);
+
+        let current = match self.history.current_streak() {
+            Some((result, count)) => tr!("stats.streak", result = result.name(), count = count),
+            None => "-".to_string(),
+        };
+        let streak_text = Text::from(vec![
+            Line::from(tr!("stats.current_streak", streak = current)),
+            Line::from(tr!(
+                "stats.best_streak",
+                count = self.history.best_win_streak()
+            )),
+        ]);
+
+        let items = games.iter().rev().map(GameRecord::summary_line);
+        let list = List::new(items).highlight_style(Style::new().bg(Color::Blue));
+
+        frame.render_widget(Text::from(t("stats.title")).centered(), title);
+        frame.render_widget(table, summary);
+        frame.render_widget(streak_text, streaks);
+        frame.render_widget(Text::from(t("stats.history")), history_title);
+        frame.render_stateful_widget(list, history, &mut self.state);
+        frame.render_widget(Text::from(t("stats.help")), help);
+    }
+}
+
+/// Step-by-step replay of a game from the history.
+pub struct Replay {
+    history: GameHistory,
+    index: usize,
+    /// How many moves of the game have been played on the board so far.
+    position: usize,
+    /// Result of the last export, shown below the board.
+    message: Option<String>,
+    /// Settings picked in the menu, passed back to the statistics.
+    settings: Settings,
+}
+
+impl Replay {
+    fn new(history: GameHistory, index: usize, settings: Settings) -> Self {
+        Replay {
+            history,
+            index,
+            position: 0,
+            message: None,
+            settings,
+        }
+    }
+
+    /// Returns the position shown on the board, and the move which led to it.
+    fn board(&self) -> (Checkers, Option<Move>) {
+        let game = self.game();
+        let moves = &game.moves[..self.position];
+        let board = moves
+            .iter()
+            .fold(Checkers::new(game.color), |board, &m| board.apply_move(m));
+        (board, moves.last().copied())
+    }
+
+    /// Saves the current position as SVG and PNG, or the whole game as GIF, in the current directory.
+    fn export(&self, gif: bool) -> Result<String> {
+        let game = self.game();
+        let name = format!(
+            "warcaby-{}",
+            game.date.with_timezone(&Local).format("%Y%m%d-%H%M")
+        );
+        if gif {
+            let path = format!("{name}.gif");
+            diagram::save_gif(&Checkers::new(game.color), &game.moves, Path::new(&path))?;
+            return Ok(path);
+        }
+
+        let (board, last_move) = self.board();
+        let name = format!("{name}-{}", self.position);
+        for ext in ["svg", "png"] {
+            diagram::save(&board, last_move, Path::new(&format!("{name}.{ext}")))?;
+        }
+        Ok(format!("{name}.svg/png"))
+    }
+
+    fn game(&self) -> &GameRecord {
+        &self.history.games[self.index]
+    }
+
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<Statistics>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            match event::read()? {
+                Event::Key(e) if e.is_press() => match self.handle_key(e) {
+                    EventResult::Continue => {}
+                    EventResult::Quit => return Ok(None),
+                    EventResult::End(state) => return Ok(Some(state)),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> EventResult<Statistics> {
+        let total = self.game().moves.len();
+        match event.code {
+            KeyCode::Char('q') => return EventResult::Quit,
+            KeyCode::Esc => {
+                let history = std::mem::take(&mut self.history);
+                return EventResult::End(Statistics::with_history(history, self.settings));
+            }
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Char(' ') => {
+                self.position = (self.position + 1).min(total);
+            }
+            KeyCode::Char('h') | KeyCode::Left => self.position = self.position.saturating_sub(1),
+            KeyCode::Home => self.position = 0,
+            KeyCode::End => self.position = total,
+            KeyCode::Char(c @ ('e' | 'g')) => {
+                self.message = Some(match self.export(c == 'g') {
+                    Ok(path) => tr!("replay.saved", path = path),
+                    Err(e) => tr!("replay.export_failed", error = e),
+                });
+            }
+            _ => {}
+        }
+        EventResult::Continue
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let game = self.game();
+        let (board, last_move) = self.board();
+
+        let move_line = match last_move {
+            Some(m) => tr!(
+                "replay.move",
+                position = self.position,
+                total = game.moves.len(),
+                m = m
+            ),
+            None => tr!("replay.start"; game.moves.len()),
+        };
+        let text = Text::from(vec![
+            Line::from(game.summary_line()),
+            Line::from(move_line),
+            Line::from(t("replay.help_steps")),
+            Line::from(t("replay.help_export")),
+            Line::from(self.message.clone().unwrap_or_default()),
+        ])
+        .centered();
+
+        let area = centered(frame.area(), frame.area().width, BOARD_HEIGHT + 6);
+        let v =
+            Layout::vertical([Constraint::Length(BOARD_HEIGHT), Constraint::Length(5)]).spacing(1);
+        let [board_area, info] = v.areas(area);
+        let board_area = centered(board_area, BOARD_WIDTH, BOARD_HEIGHT);
+
+        render_position(&board, last_move, frame, board_area);
+        frame.render_widget(text, info);
+    }
+}
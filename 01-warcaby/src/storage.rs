@@ -0,0 +1,40 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use color_eyre::eyre::{Result, WrapErr};
+use directories::ProjectDirs;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Returns the path of a file in the game's data directory.
+fn data_file(name: &str) -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("pj", "s28840", "warcaby")?;
+    Some(dirs.data_dir().join(name))
+}
+
+/// Loads a value saved with [`save`].
+/// A missing file gives the default value, so the data starts from scratch.
+///
+/// Files which can't be read or parsed are an error, rather than the default value,
+/// so that saving over them doesn't lose the data.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> Result<T> {
+    let Some(path) = data_file(name) else {
+        return Ok(T::default());
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .wrap_err_with(|| format!("{} is not in the expected format", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e).wrap_err_with(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// Saves a value as JSON in the game's data directory.
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let Some(path) = data_file(name) else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string(value)?)?;
+    Ok(())
+}
@@ -0,0 +1,386 @@
+//! Building blocks for playing turn-based board games against the AI in the terminal.
+//!
+//! A game plugs into the harness by implementing [`TuiGame`] on top of its [`minimax::Game`] rules.
+//! The screens ([`Menu`], [`Match`] and [`GameEnded`]) each have a `run` method that takes over
+//! the terminal until the screen is done, so an application is a small state machine
+//! switching between them.
+
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use i18n::t;
+use minimax::{Game, Strategy};
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Text},
+    widgets::{List, ListState},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::centered,
+    clock::{Clock, Side, TimeControl},
+};
+
+#[must_use]
+pub enum EventResult<S> {
+    Continue,
+    Quit,
+    End(S),
+}
+
+/// Result of a finished game, from the point of view of the human player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameResult {
+    Won,
+    Lost,
+    Draw,
+}
+
+impl GameResult {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameResult::Won => t("result.won"),
+            GameResult::Lost => t("result.lost"),
+            GameResult::Draw => t("result.draw"),
+        }
+    }
+}
+
+/// A game which can be played by a human against the AI using [`Match`].
+pub trait TuiGame: Game {
+    /// State of the human player's input, e.g. a cursor over the board.
+    type Input: Default;
+
+    /// Handles a key press of the human player.
+    /// Returns the move, once the player confirms one.
+    fn handle_key(input: &mut Self::Input, state: &Self::S, event: KeyEvent) -> Option<Self::M>;
+
+    /// Draws the game within the given area, together with the human player's input.
+    fn render(input: &Self::Input, state: &Self::S, frame: &mut Frame, area: Rect);
+}
+
+/// Applies a move to the state, regardless of whether the game rules
+/// return a new state or update it in place.
+pub fn apply<G: Game>(state: &mut G::S, m: G::M) {
+    if let Some(new_state) = G::apply(state, m) {
+        *state = new_state;
+    }
+}
+
+/// Screen picking one of the options from a list.
+pub struct Menu {
+    title: String,
+    options: Vec<String>,
+    state: ListState,
+}
+
+impl Menu {
+    pub fn new(
+        title: impl Into<String>,
+        options: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Menu {
+            title: title.into(),
+            options: options.into_iter().map(Into::into).collect(),
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    /// Returns the index of the picked option, or `None` if the user quit.
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<usize>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            match event::read()? {
+                Event::Key(e) if e.is_press() => match self.handle_key(e) {
+                    EventResult::Continue => {}
+                    EventResult::Quit => return Ok(None),
+                    EventResult::End(index) => return Ok(Some(index)),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> EventResult<usize> {
+        match event.code {
+            KeyCode::Char('q') => return EventResult::Quit,
+            KeyCode::Char('j') | KeyCode::Down => self.state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.state.select_previous(),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(index) = self.state.selected()
+                    && index < self.options.len()
+                {
+                    return EventResult::End(index);
+                }
+            }
+            _ => {}
+        }
+        EventResult::Continue
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let v = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]);
+        let [title, menu] = v.areas(frame.area());
+
+        let options = self
+            .options
+            .iter()
+            .map(|line| Text::from(line.as_str()).centered());
+        let list = List::new(options).highlight_style(Style::new().bg(Color::Blue));
+
+        frame.render_widget(Text::from(self.title.as_str()).centered(), title);
+        frame.render_stateful_widget(list, menu, &mut self.state);
+    }
+}
+
+/// Width of the column with the clocks, shown next to the game.
+const CLOCK_WIDTH: u16 = 12;
+
+/// How often the screen is redrawn while a clock is running.
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+
+/// A game between the human player and the AI.
+pub struct Match<G: TuiGame> {
+    state: G::S,
+    input: G::Input,
+    ai: Box<dyn Strategy<G>>,
+    human_turn: bool,
+    moves: Vec<G::M>,
+    started: Instant,
+    clock: Option<Clock>,
+    ended_on_time: bool,
+}
+
+impl<G: TuiGame> Match<G> {
+    /// Starts a game without a clock from the given state.
+    pub fn new(state: G::S, human_first: bool, ai: Box<dyn Strategy<G>>) -> Self {
+        Self::with_time_control(state, human_first, ai, TimeControl::Unlimited)
+    }
+
+    /// Starts a game from the given state, with both sides playing under the given time control.
+    /// The AI spends on each move a share of its remaining time.
+    pub fn with_time_control(
+        state: G::S,
+        human_first: bool,
+        ai: Box<dyn Strategy<G>>,
+        time_control: TimeControl,
+    ) -> Self {
+        let first = if human_first { Side::Human } else { Side::Ai };
+        Match {
+            state,
+            input: G::Input::default(),
+            ai,
+            human_turn: human_first,
+            moves: Vec::new(),
+            started: Instant::now(),
+            clock: Clock::new(time_control, first),
+            ended_on_time: false,
+        }
+    }
+
+    pub fn state(&self) -> &G::S {
+        &self.state
+    }
+
+    /// Returns all moves made so far, by both sides.
+    pub fn moves(&self) -> &[G::M] {
+        &self.moves
+    }
+
+    /// Returns how long the game has been going on.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Returns whether the game ended by one of the sides running out of time.
+    pub fn ended_on_time(&self) -> bool {
+        self.ended_on_time
+    }
+
+    /// Returns the result of the game, or `None` if the user quit.
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<GameResult>> {
+        if let Some(clock) = &mut self.clock {
+            clock.start();
+        }
+
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            // The board is drawn before the AI starts thinking, so the human player's move shows up right away.
+            let res = if !self.human_turn {
+                self.ai_turn()
+            } else if self.clock.is_some() && !event::poll(CLOCK_TICK)? {
+                self.check_flag()
+            } else {
+                match event::read()? {
+                    Event::Key(e) if e.is_press() => self.handle_key(e),
+                    _ => EventResult::Continue,
+                }
+            };
+
+            match res {
+                EventResult::Continue => {}
+                EventResult::Quit => return Ok(None),
+                EventResult::End(result) => return Ok(Some(result)),
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: KeyEvent) -> EventResult<GameResult> {
+        if event.code == KeyCode::Char('q') {
+            return EventResult::Quit;
+        }
+        // A move made after the time ran out doesn't count.
+        if let res @ EventResult::End(_) = self.check_flag() {
+            return res;
+        }
+
+        debug_assert!(self.human_turn);
+        match G::handle_key(&mut self.input, &self.state, event) {
+            Some(m) => self.play(m),
+            None => EventResult::Continue,
+        }
+    }
+
+    fn ai_turn(&mut self) -> EventResult<GameResult> {
+        // Let the AI pick its move and apply it, then check for end condition.
+        debug_assert!(!self.human_turn);
+        if let Some(clock) = &self.clock {
+            self.ai.set_timeout(clock.ai_budget());
+        }
+        // A search stopped by the clock before it got through even the shallowest level
+        // has no move to give, so any valid one is played instead.
+        let m = self.ai.choose_move(&self.state).unwrap_or_else(|| {
+            let mut moves = Vec::new();
+            G::generate_moves(&self.state, &mut moves);
+            moves[0]
+        });
+
+        if let res @ EventResult::End(_) = self.check_flag() {
+            return res;
+        }
+        self.play(m)
+    }
+
+    /// Ends the game if the side to move ran out of time.
+    fn check_flag(&mut self) -> EventResult<GameResult> {
+        let Some(side) = self.clock.as_ref().and_then(Clock::flag_fallen) else {
+            return EventResult::Continue;
+        };
+        self.ended_on_time = true;
+        EventResult::End(match side {
+            Side::Human => GameResult::Lost,
+            Side::Ai => GameResult::Won,
+        })
+    }
+
+    /// Applies a move of the side to move, and checks whether it ended the game.
+    fn play(&mut self, m: G::M) -> EventResult<GameResult> {
+        apply::<G>(&mut self.state, m);
+        self.moves.push(m);
+        if let Some(clock) = &mut self.clock {
+            clock.press();
+        }
+
+        let human_moved = self.human_turn;
+        self.human_turn = !self.human_turn;
+
+        let (win, loss) = if human_moved {
+            (GameResult::Won, GameResult::Lost)
+        } else {
+            (GameResult::Lost, GameResult::Won)
+        };
+        match G::get_winner(&self.state) {
+            Some(minimax::Winner::PlayerJustMoved) => EventResult::End(win),
+            Some(minimax::Winner::PlayerToMove) => EventResult::End(loss),
+            Some(minimax::Winner::Draw) => EventResult::End(GameResult::Draw),
+            None => EventResult::Continue,
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let Some(clock) = &self.clock else {
+            G::render(&self.input, &self.state, frame, frame.area());
+            return;
+        };
+
+        let h = Layout::horizontal([Constraint::Fill(1), Constraint::Length(CLOCK_WIDTH)]);
+        let [game, clocks] = h.areas(frame.area());
+        G::render(&self.input, &self.state, frame, game);
+
+        let lines = [Side::Ai, Side::Human].into_iter().flat_map(|side| {
+            let name = match side {
+                Side::Human => t("clock.you"),
+                Side::Ai => t("clock.ai"),
+            };
+            let style = if side == clock.to_move() {
+                Style::new().add_modifier(Modifier::BOLD).fg(Color::Yellow)
+            } else {
+                Style::new()
+            };
+            [
+                Line::from(name).style(style),
+                Line::from(format_clock(clock.remaining(side))).style(style),
+                Line::from(""),
+            ]
+        });
+        let area = centered(clocks, CLOCK_WIDTH, 6);
+        frame.render_widget(Text::from_iter(lines), area);
+    }
+}
+
+/// Formats the time left on a clock, showing tenths of a second when it's about to run out.
+fn format_clock(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    if secs < 10 {
+        format!("0:0{secs}.{}", remaining.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Screen showing a message about how the game ended.
+pub struct GameEnded {
+    pub result: GameResult,
+    /// Whether the game was decided by one of the sides running out of time.
+    pub on_time: bool,
+    /// Shown below the result, e.g. when the game couldn't be saved.
+    pub message: Option<String>,
+}
+
+impl GameEnded {
+    /// Waits for a key press. Returns `false` if the user wants to quit,
+    /// and `true` if they want to continue (e.g. back to the menu).
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<bool> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            match event::read()? {
+                Event::Key(e) if e.is_press() => return Ok(e.code != KeyCode::Char('q')),
+                _ => {}
+            }
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let message = match (self.result, self.on_time) {
+            (GameResult::Won, false) => t("game.won"),
+            (GameResult::Won, true) => t("game.won_on_time"),
+            (GameResult::Lost, false) => t("game.lost"),
+            (GameResult::Lost, true) => t("game.lost_on_time"),
+            (GameResult::Draw, _) => t("game.draw"),
+        };
+        let mut text = Text::from(message);
+        if let Some(extra) = &self.message {
+            text.push_line("");
+            text.push_line(extra.as_str());
+        }
+        let text = text.centered();
+        frame.render_widget(text, frame.area());
+    }
+}
@@ -0,0 +1,288 @@
+//! Learned position evaluation: a small neural network, trained on self-play games
+//! to predict which side wins from a given position.
+//!
+//! The network is trained by the `train-value-net` binary, and loaded with [`ValueNetEval::load`]
+//! as an alternative to [`CheckerEval`](crate::ai::CheckerEval). Everything runs on the CPU.
+//! Available only with the `value-net` feature.
+
+use std::{marker::PhantomData, path::PathBuf};
+
+use burn::{
+    Tensor,
+    backend::{NdArray, ndarray::NdArrayDevice},
+    config::Config,
+    data::{dataloader::batcher::Batcher, dataset::Dataset},
+    module::Module,
+    nn::{
+        Linear, LinearConfig, Relu,
+        loss::{MseLoss, Reduction},
+    },
+    prelude::Backend,
+    record::{CompactRecorder, RecorderError},
+    tensor::{ElementConversion, activation::tanh, backend::AutodiffBackend},
+    train::{RegressionOutput, TrainOutput, TrainStep, ValidStep},
+};
+use indicatif::ProgressBar;
+use minimax::{Evaluator, Strategy};
+use rand::seq::IndexedRandom;
+
+use crate::{
+    ai::CheckersRules,
+    game::{BOARD_SIZE, Checkers, Piece, Turn, Winner},
+    mcts::{Mcts, MctsOptions},
+};
+
+/// Number of dark cells, the only ones pieces can stand on.
+const DARK_CELLS: usize = BOARD_SIZE * BOARD_SIZE / 2;
+/// Size of the network input: one value for each dark cell, for each side.
+pub const INPUT_SIZE: usize = 2 * DARK_CELLS;
+
+/// Evaluation of a position the network is sure the side to move wins.
+/// Kept well below the evaluation of an actual win, so the search still prefers winning outright.
+const EVAL_SCALE: f32 = 1000.;
+
+/// Encodes the position from the point of view of the side to move.
+///
+/// The first half of the input marks the pieces of the side to move, the second half those
+/// of the opponent. The board is rotated so the side to move always goes up the board,
+/// which lets the network learn a single point of view instead of two.
+pub fn encode(state: &Checkers) -> [f32; INPUT_SIZE] {
+    let to_move = state.piece_for_turn();
+    let rotate = state.turn() == Turn::Ai;
+
+    let mut input = [0.; INPUT_SIZE];
+    for (y, x, piece) in state.iter_pieces() {
+        let (y, x) = if rotate {
+            (BOARD_SIZE - 1 - y, BOARD_SIZE - 1 - x)
+        } else {
+            (y, x)
+        };
+        // Each row has only every other cell dark, so `x / 2` numbers them within the row.
+        let cell = y * BOARD_SIZE / 2 + x / 2;
+        let side = if piece == to_move { 0 } else { DARK_CELLS };
+        input[side + cell] = 1.;
+    }
+    input
+}
+
+#[derive(Module, Debug)]
+pub struct ValueNet<B: Backend> {
+    l1: Linear<B>,
+    l2: Linear<B>,
+    l3: Linear<B>,
+    activation: Relu,
+}
+
+#[derive(Config, Debug)]
+pub struct ValueNetConfig {
+    #[config(default = 64)]
+    hidden_size: usize,
+}
+
+impl ValueNetConfig {
+    pub fn init<B: Backend>(&self, device: &B::Device) -> ValueNet<B> {
+        ValueNet {
+            l1: LinearConfig::new(INPUT_SIZE, self.hidden_size).init(device),
+            l2: LinearConfig::new(self.hidden_size, self.hidden_size).init(device),
+            l3: LinearConfig::new(self.hidden_size, 1).init(device),
+            activation: Relu::new(),
+        }
+    }
+}
+
+impl<B: Backend> ValueNet<B> {
+    /// Returns the expected result for the side to move in each of the encoded positions,
+    /// from -1 (certain loss) to 1 (certain win).
+    pub fn forward(&self, input: Tensor<B, 2>) -> Tensor<B, 2> {
+        let x = self.activation.forward(self.l1.forward(input));
+        let x = self.activation.forward(self.l2.forward(x));
+        tanh(self.l3.forward(x))
+    }
+
+    pub fn forward_regression(
+        &self,
+        inputs: Tensor<B, 2>,
+        targets: Tensor<B, 2>,
+    ) -> RegressionOutput<B> {
+        let output = self.forward(inputs);
+        let loss = MseLoss::new().forward(output.clone(), targets.clone(), Reduction::Mean);
+
+        RegressionOutput::new(loss, output, targets)
+    }
+}
+
+/// Evaluator backed by a trained [`ValueNet`].
+#[derive(Clone)]
+pub struct ValueNetEval {
+    model: ValueNet<NdArray>,
+    device: NdArrayDevice,
+}
+
+impl ValueNetEval {
+    /// Loads a network saved by the `train-value-net` binary.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, RecorderError> {
+        let device = NdArrayDevice::default();
+        let model = ValueNetConfig::new().init(&device).load_file(
+            path,
+            &CompactRecorder::new(),
+            &device,
+        )?;
+        Ok(ValueNetEval { model, device })
+    }
+}
+
+impl Evaluator for ValueNetEval {
+    type G = CheckersRules;
+
+    fn evaluate(&self, s: &<Self::G as minimax::Game>::S) -> minimax::Evaluation {
+        let input = Tensor::<NdArray, 1>::from_floats(encode(s), &self.device).unsqueeze();
+        let value: f32 = self.model.forward(input).into_scalar().elem();
+        (value * EVAL_SCALE) as minimax::Evaluation
+    }
+}
+
+// training
+
+/// A position seen during self-play, labeled with how the game ended.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub input: [f32; INPUT_SIZE],
+    /// Result of the game for the side to move: 1 for a win, -1 for a loss, 0 for a draw.
+    pub outcome: f32,
+}
+
+#[derive(Clone)]
+pub struct SampleDataset {
+    samples: Vec<Sample>,
+}
+
+impl Dataset<Sample> for SampleDataset {
+    fn get(&self, index: usize) -> Option<Sample> {
+        self.samples.get(index).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+}
+
+impl SampleDataset {
+    pub fn split(self, ratio: f64) -> (SampleDataset, SampleDataset) {
+        assert!((0. ..=1.).contains(&ratio));
+        let split_idx = (self.len() as f64 * ratio) as usize;
+        let (a, b) = self.samples.split_at(split_idx);
+        let a = SampleDataset {
+            samples: a.to_vec(),
+        };
+        let b = SampleDataset {
+            samples: b.to_vec(),
+        };
+        (a, b)
+    }
+}
+
+/// Plays `games` games of MCTS against itself, and collects every position along the way.
+///
+/// The first `opening` moves of each game are random, so the games don't all look the same.
+pub fn self_play(games: u32, rollouts: u32, opening: u32) -> SampleDataset {
+    let options = MctsOptions {
+        iterations: rollouts,
+        ..Default::default()
+    };
+    let mut ai = Mcts::new(options);
+    let mut rng = rand::rng();
+    let mut samples = Vec::new();
+
+    let progress = ProgressBar::new(games as u64);
+    for _ in 0..games {
+        let mut state = Checkers::new(Piece::White);
+        let mut positions = Vec::new();
+        let winner = loop {
+            match state.get_winner() {
+                Winner::InProgress => {}
+                winner => break winner,
+            }
+
+            let m = if positions.len() < opening as usize {
+                *state
+                    .valid_moves(state.piece_for_turn())
+                    .choose(&mut rng)
+                    .unwrap()
+            } else {
+                ai.choose_move(&state).unwrap()
+            };
+            positions.push((encode(&state), state.turn()));
+            state = state.apply_move(m);
+        };
+
+        samples.extend(positions.into_iter().map(|(input, turn)| Sample {
+            input,
+            outcome: match winner {
+                Winner::Won(winner) if winner == turn => 1.,
+                Winner::Won(_) => -1.,
+                Winner::Draw | Winner::InProgress => 0.,
+            },
+        }));
+        progress.inc(1);
+    }
+    progress.finish();
+
+    SampleDataset { samples }
+}
+
+#[derive(Clone, Debug)]
+pub struct SampleBatch<B: Backend> {
+    pub inputs: Tensor<B, 2>,
+    pub targets: Tensor<B, 2>,
+}
+
+#[derive(Clone)]
+pub struct SampleBatcher<B: Backend> {
+    _backend: PhantomData<B>,
+}
+
+impl<B: Backend> SampleBatcher<B> {
+    pub fn new() -> Self {
+        SampleBatcher {
+            _backend: PhantomData,
+        }
+    }
+}
+
+impl<B: Backend> Default for SampleBatcher<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Backend> Batcher<B, Sample, SampleBatch<B>> for SampleBatcher<B> {
+    fn batch(&self, items: Vec<Sample>, device: &B::Device) -> SampleBatch<B> {
+        let inputs = items
+            .iter()
+            .map(|item| Tensor::<B, 1>::from_floats(item.input, device))
+            .collect();
+        let targets = items
+            .iter()
+            .map(|item| Tensor::<B, 1>::from_floats([item.outcome], device))
+            .collect();
+
+        SampleBatch {
+            inputs: Tensor::stack(inputs, 0),
+            targets: Tensor::stack(targets, 0),
+        }
+    }
+}
+
+impl<B: AutodiffBackend> TrainStep<SampleBatch<B>, RegressionOutput<B>> for ValueNet<B> {
+    fn step(&self, batch: SampleBatch<B>) -> TrainOutput<RegressionOutput<B>> {
+        let item = self.forward_regression(batch.inputs, batch.targets);
+
+        TrainOutput::new(self, item.loss.backward(), item)
+    }
+}
+
+impl<B: Backend> ValidStep<SampleBatch<B>, RegressionOutput<B>> for ValueNet<B> {
+    fn step(&self, batch: SampleBatch<B>) -> RegressionOutput<B> {
+        self.forward_regression(batch.inputs, batch.targets)
+    }
+}
@@ -0,0 +1,57 @@
+//! Checks that the AI players stop thinking once the time for their move runs out,
+//! without playing stronger than their difficulty.
+
+use std::time::{Duration, Instant};
+
+use warcaby::{
+    ai::{Difficulty, Engine},
+    clock::TimeControl,
+    game::{Checkers, Piece},
+};
+
+const BUDGET: Duration = Duration::from_millis(10);
+
+/// How long the search may take to notice the timeout and return its move.
+const MARGIN: Duration = Duration::from_millis(50);
+
+#[test]
+fn ai_stops_thinking_when_the_budget_runs_out() {
+    let time_control = TimeControl::SuddenDeath {
+        base: Duration::from_secs(60),
+    };
+    let state = Checkers::new(Piece::White);
+    for engine in Engine::ALL {
+        let mut ai = engine.strategy(Difficulty::Hard, time_control);
+        ai.set_timeout(BUDGET);
+
+        let start = Instant::now();
+        let m = ai.choose_move(&state);
+        let elapsed = start.elapsed();
+
+        assert!(m.is_some(), "{} found no move", engine.name());
+        assert!(
+            elapsed <= BUDGET + MARGIN,
+            "{} thought for {elapsed:?} with a budget of {BUDGET:?}",
+            engine.name()
+        );
+    }
+}
+
+#[test]
+fn clock_does_not_deepen_the_search() {
+    let time_control = TimeControl::Fischer {
+        base: Duration::from_secs(60),
+        increment: Duration::from_secs(1),
+    };
+    let state = Checkers::new(Piece::White);
+    let mut ai = Engine::Negamax.strategy(Difficulty::Easy, time_control);
+    ai.set_timeout(Duration::from_secs(5));
+
+    assert!(ai.choose_move(&state).is_some());
+    let depth = ai.principal_variation().len();
+    assert_eq!(
+        depth,
+        Difficulty::Easy.depth() as usize,
+        "searched {depth} moves deep"
+    );
+}
@@ -0,0 +1,305 @@
+//! Offline evaluation of the recommendation engine: trains it on part of the ratings,
+//! scores it on the rest, and compares different hyperparameters.
+//!
+//! ```bash
+//! cargo run --release --bin evaluate -- --folds 5 --grid
+//! ```
+//!
+//! The ratings are split into `--folds` parts (5 by default), or with `--leave-one-out`
+//! one rating of every user is held out. For every set of hyperparameters the table shows
+//! RMSE and MAE of the predicted ratings, and precision, recall and NDCG of the top `--top`
+//! recommendations (10 by default), where a movie counts as liked if it was rated at least
+//! [`RELEVANT_RATING`](rekomendacje::evaluation::RELEVANT_RATING).
+//!
+//! `--algorithm` picks the algorithms to compare, as a comma separated list of names accepted
+//! by `rekomendacje --algorithm`, or `all`. By default only matrix factorization is evaluated.
+//! The search only applies to matrix factorization and the hybrid model,
+//! the other algorithms don't use these settings.
+//! Without `--grid` or `--random N` only the current settings are evaluated, which are the
+//! defaults overridden by `REC_ITERATIONS`, `REC_FACTORS`, `REC_REGULARIZATION`,
+//! `REC_NORMALIZATION`, `REC_MIN_MOVIE_RATINGS` and `REC_MIN_USER_RATINGS`.
+//! `--normalization` compares normalizations of the ratings, as a comma separated list
+//! (`none`, `mean`, `zscore`, `rank`) or `all`, each with every other setting.
+//!
+//! The recommendations are re-ranked for diversity as in the interface, and `--diversity`
+//! compares a comma separated list of settings from 0 to 1. Along with the accuracy,
+//! the table shows the intra-list diversity (ILD) and novelty of the recommendations.
+//! The data is taken from `--data` or `REC_DATA_DIR`, and the embedded data otherwise.
+
+use std::{path::PathBuf, process::exit};
+
+use color_eyre::eyre::Result;
+use indicatif::ProgressBar;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use rekomendacje::{
+    data::Data,
+    diversity::DEFAULT_DIVERSITY,
+    evaluation::{Metrics, Split, split},
+    model::Hyperparams,
+    preprocessing::Normalization,
+    recommender::Algorithm,
+};
+
+const FACTORS_GRID: [u32; 4] = [16, 32, 64, 128];
+const ITERATIONS_GRID: [u32; 4] = [128, 256, 512, 1024];
+const REGULARIZATION_GRID: [f32; 4] = [0.01, 0.05, 0.1, 0.5];
+
+fn bail_usage() -> ! {
+    eprintln!(
+        "usage: evaluate [--data directory] [--algorithm name,...|all] [--folds N | --leave-one-out] [--top N] [--grid | --random N] [--normalization name,...|all] [--diversity X,...] [--seed N]"
+    );
+    exit(1);
+}
+
+fn unwrap_usage<T>(res: Result<T, String>) -> T {
+    match res {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}");
+            bail_usage();
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, name: &str) -> T {
+    let value = args.next().unwrap_or_else(|| bail_usage());
+    unwrap_usage(
+        value
+            .parse()
+            .map_err(|_| format!("invalid {name} {value:?}")),
+    )
+}
+
+#[derive(Clone, Copy)]
+enum Search {
+    /// Only the current settings.
+    None,
+    /// Every combination of the values in the grids.
+    Grid,
+    /// This many random settings, spread evenly on a logarithmic scale.
+    Random(usize),
+}
+
+fn candidates(
+    search: Search,
+    current: Hyperparams,
+    normalizations: &[Normalization],
+    rng: &mut SmallRng,
+) -> Vec<Hyperparams> {
+    let mut candidates = vec![current];
+    match search {
+        Search::None => {}
+        Search::Grid => {
+            for factors in FACTORS_GRID {
+                for iterations in ITERATIONS_GRID {
+                    for regularization in REGULARIZATION_GRID {
+                        candidates.push(Hyperparams {
+                            iterations,
+                            factors,
+                            regularization,
+                            ..current
+                        });
+                    }
+                }
+            }
+        }
+        Search::Random(count) => {
+            for _ in 0..count {
+                candidates.push(Hyperparams {
+                    iterations: 1 << rng.random_range(5..=11),
+                    factors: 1 << rng.random_range(3..=8),
+                    regularization: 10f32.powf(rng.random_range(-3.0..=0.0)),
+                    ..current
+                });
+            }
+        }
+    }
+    // Every setting is tried with every normalization.
+    let candidates = candidates.into_iter().flat_map(|params| {
+        normalizations.iter().map(move |&normalization| {
+            let mut params = params;
+            params.preprocessing.normalization = normalization;
+            params
+        })
+    });
+    // The current settings may also be in the grid.
+    let mut unique = vec![current];
+    for params in candidates {
+        if !unique.contains(&params) {
+            unique.push(params);
+        }
+    }
+    unique
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let mut args = std::env::args().skip(1);
+    let mut data_dir = std::env::var("REC_DATA_DIR").ok().map(PathBuf::from);
+    let mut split_kind = Split::KFold(5);
+    let mut top = 10;
+    let mut search = Search::None;
+    let mut seed = 0;
+    let mut algorithms = vec![Algorithm::MatrixFactorization];
+    let mut normalizations = None;
+    let mut diversities = vec![DEFAULT_DIVERSITY];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data" => data_dir = Some(args.next().unwrap_or_else(|| bail_usage()).into()),
+            "--folds" => match parse_value(&mut args, "--folds") {
+                folds @ 2.. => split_kind = Split::KFold(folds),
+                _ => unwrap_usage(Err("--folds must be at least 2".to_string())),
+            },
+            "--algorithm" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                algorithms = match value.as_str() {
+                    "all" => Algorithm::ALL.to_vec(),
+                    _ => unwrap_usage(value.split(',').map(str::parse).collect()),
+                };
+            }
+            "--leave-one-out" => split_kind = Split::LeaveOneOut,
+            "--top" => top = parse_value(&mut args, "--top"),
+            "--grid" => search = Search::Grid,
+            "--random" => search = Search::Random(parse_value(&mut args, "--random")),
+            "--normalization" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                normalizations = Some(match value.as_str() {
+                    "all" => Normalization::ALL.to_vec(),
+                    _ => unwrap_usage(value.split(',').map(str::parse).collect()),
+                });
+            }
+            "--diversity" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                diversities = unwrap_usage(
+                    value
+                        .split(',')
+                        .map(|x| match x.parse() {
+                            Ok(x @ 0.0..=1.0) => Ok(x),
+                            _ => Err(format!("invalid diversity {x:?}, expected 0 to 1")),
+                        })
+                        .collect(),
+                );
+            }
+            "--seed" => seed = parse_value(&mut args, "--seed"),
+            _ => bail_usage(),
+        }
+    }
+
+    let data = Data::load_or_embedded(data_dir.as_deref())?;
+    let ratings = data.ratings();
+    let folds = split(&ratings, split_kind, seed);
+
+    let current = Hyperparams::from_env();
+    let normalizations = normalizations.unwrap_or(vec![current.preprocessing.normalization]);
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut candidates: Vec<(Algorithm, Hyperparams)> = Vec::new();
+    for algorithm in algorithms {
+        if algorithm.uses_hyperparams() {
+            let params = self::candidates(search, current, &normalizations, &mut rng);
+            candidates.extend(params.into_iter().map(|params| (algorithm, params)));
+        } else {
+            candidates.push((algorithm, current));
+        }
+    }
+
+    println!(
+        "Evaluating {} settings on {} ratings, in {} folds",
+        candidates.len() * diversities.len(),
+        ratings.len(),
+        folds.len()
+    );
+    println!(
+        "Matrix factorization is trained on movies with at least {} ratings, and users with at least {}",
+        current.preprocessing.min_movie_ratings, current.preprocessing.min_user_ratings
+    );
+    let bar = ProgressBar::new((candidates.len() * folds.len()) as _);
+    let mut results: Vec<(Algorithm, Hyperparams, f32, Metrics)> = Vec::new();
+    for (algorithm, params) in candidates {
+        // Re-ranking doesn't change the model, so each one is trained once for all diversities.
+        let mut scores = vec![Vec::new(); diversities.len()];
+        for fold in &folds {
+            let model = algorithm.train(&fold.train, &data.movies, params);
+            bar.inc(1);
+            for (scores, &diversity) in scores.iter_mut().zip(&diversities) {
+                scores.push(Metrics::evaluate(
+                    model.as_ref(),
+                    &data.movies,
+                    fold,
+                    top,
+                    diversity,
+                ));
+            }
+        }
+        for (scores, &diversity) in scores.iter().zip(&diversities) {
+            results.push((algorithm, params, diversity, Metrics::mean(scores)));
+        }
+    }
+    bar.finish_and_clear();
+
+    results.sort_by(|(_, _, _, a), (_, _, _, b)| a.rmse.total_cmp(&b.rmse));
+
+    println!(
+        "{:<18} {:>7} {:>10} {:>14} {:>13} {:>9} {:>7} {:>7} {:>7} {:>7} {:>7} {:>7} {:>7}",
+        "Algorithm",
+        "Factors",
+        "Iterations",
+        "Regularization",
+        "Normalization",
+        "Diversity",
+        "RMSE",
+        "MAE",
+        format!("P@{top}"),
+        format!("R@{top}"),
+        format!("NDCG@{top}"),
+        "ILD",
+        "Novelty",
+    );
+    for (algorithm, params, diversity, metrics) in &results {
+        let (factors, iterations, regularization, normalization, marker) =
+            if algorithm.uses_hyperparams() {
+                (
+                    params.factors.to_string(),
+                    params.iterations.to_string(),
+                    format!("{:.4}", params.regularization),
+                    params.preprocessing.normalization.to_string(),
+                    if *params == current && *diversity == DEFAULT_DIVERSITY {
+                        " (current)"
+                    } else {
+                        ""
+                    },
+                )
+            } else {
+                let none = || "-".to_string();
+                (none(), none(), none(), none(), "")
+            };
+        println!(
+            "{:<18} {factors:>7} {iterations:>10} {regularization:>14} {normalization:>13} {diversity:>9.1} {:>7.3} {:>7.3} {:>7.3} {:>7.3} {:>7.3} {:>7.3} {:>7.3}{marker}",
+            algorithm.to_string(),
+            metrics.rmse,
+            metrics.mae,
+            metrics.precision,
+            metrics.recall,
+            metrics.ndcg,
+            metrics.diversity,
+            metrics.novelty,
+        );
+    }
+
+    println!();
+    if let [(best, _, _, _), _, ..] = results.as_slice() {
+        println!("Lowest RMSE: {best}");
+    }
+    let best_params = results
+        .iter()
+        .find(|(algorithm, _, _, _)| algorithm.uses_hyperparams());
+    if let Some((_, best, _, _)) = best_params {
+        println!("Recommended defaults, with the lowest RMSE:");
+        println!("REC_FACTORS={}", best.factors);
+        println!("REC_ITERATIONS={}", best.iterations);
+        println!("REC_REGULARIZATION={:.4}", best.regularization);
+        println!("REC_NORMALIZATION={}", best.preprocessing.normalization);
+    }
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! Converts a MovieLens dataset into the format of `normalize`, so the program and `evaluate`
+//! can be tried on far more ratings than the survey has.
+//!
+//! ```bash
+//! cargo run --release --bin movielens -- ml-latest-small data-movielens
+//! cargo run --release --bin evaluate -- --data data-movielens
+//! ```
+//!
+//! The input directory needs `ratings.csv`, `movies.csv` and `links.csv` from one of
+//! the MovieLens datasets in the CSV format, e.g. `ml-latest-small` with 100k ratings,
+//! or `ml-20m`. Movies are identified by their IMDb ids from `links.csv`, and movies without
+//! one are left out. Ratings from 0.5 to 5 stars are doubled, to the 1-10 scale.
+//!
+//! The first `--users` users (20 by default) can be picked in the program, the rest are
+//! only used for training. MovieLens doesn't list directors or posters, so they're left out.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::exit,
+};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use rekomendacje::{ImdbTitle, Rating, User, data::Data};
+
+const RATINGS_FILE: &str = "ratings.csv";
+const MOVIES_FILE: &str = "movies.csv";
+const LINKS_FILE: &str = "links.csv";
+
+/// Articles which MovieLens moves to the end of the title, as in `Matrix, The`.
+const ARTICLES: [&str; 13] = [
+    "The", "A", "An", "La", "Le", "Les", "Il", "Die", "Der", "Das", "El", "Los", "Las",
+];
+
+fn bail_usage() -> ! {
+    eprintln!("usage: movielens [--users N] [input directory] [output directory]");
+    exit(1);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MovieRecord {
+    movie_id: u32,
+    title: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkRecord {
+    movie_id: u32,
+    /// Digits of the IMDb id, without the `tt` prefix. Read as text to keep the leading zeros.
+    imdb_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RatingRecord {
+    user_id: u32,
+    movie_id: u32,
+    rating: f32,
+}
+
+fn read<T: DeserializeOwned>(dir: &Path, name: &str) -> Result<Vec<T>> {
+    let path = dir.join(name);
+    let mut reader = csv::Reader::from_path(&path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    reader
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .wrap_err_with(|| format!("{} is not in the expected format", path.display()))
+}
+
+/// Splits a MovieLens title, such as `City of Lost Children, The (Cité des enfants perdus, La)
+/// (1995)`, into the title, the original title if given, and the year.
+fn parse_title(title: &str) -> (String, Option<String>, Option<u32>) {
+    let mut title = title.trim();
+    let mut year = None;
+    if let Some((rest, last)) = title.rsplit_once(" (")
+        && let Some(parsed) = last.strip_suffix(')').and_then(|y| y.parse().ok())
+    {
+        (title, year) = (rest, Some(parsed));
+    }
+    let mut original = None;
+    if let Some((rest, last)) = title.rsplit_once(" (")
+        && let Some(inner) = last.strip_suffix(')')
+    {
+        // Other parentheses are alternative titles, but `a.k.a.` ones are just other names.
+        if !inner.starts_with("a.k.a.") {
+            original = Some(move_article(inner));
+        }
+        title = rest;
+    }
+    (move_article(title), original, year)
+}
+
+/// Moves an article from the end of the title back to the front.
+fn move_article(title: &str) -> String {
+    for article in ARTICLES {
+        if let Some(rest) = title.strip_suffix(&format!(", {article}")) {
+            return format!("{article} {rest}");
+        }
+    }
+    title.to_string()
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let mut args = std::env::args().skip(1);
+    let mut shown_users = 20;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--users" => {
+                let value = args.next().unwrap_or_else(|| bail_usage());
+                shown_users = value.parse().unwrap_or_else(|_| {
+                    eprintln!("invalid --users {value:?}");
+                    bail_usage()
+                });
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [input, output] = <[PathBuf; 2]>::try_from(paths).unwrap_or_else(|_| bail_usage());
+
+    let imdb_ids: HashMap<u32, String> = read::<LinkRecord>(&input, LINKS_FILE)?
+        .into_iter()
+        .filter_map(|link| {
+            let digits = link.imdb_id?;
+            let number: u32 = digits.trim().parse().ok()?;
+            Some((link.movie_id, format!("tt{number:07}")))
+        })
+        .collect();
+
+    // A few IMDb titles are listed twice in MovieLens, only the first one is kept.
+    let mut movies = BTreeMap::new();
+    for record in read::<MovieRecord>(&input, MOVIES_FILE)? {
+        let Some(id) = imdb_ids.get(&record.movie_id) else {
+            continue;
+        };
+        let (primary_title, original_title, start_year) = parse_title(&record.title);
+        movies.entry(id.clone()).or_insert(ImdbTitle {
+            id: id.clone(),
+            primary_title,
+            original_title,
+            primary_image: None,
+            directors: Vec::new(),
+            plot: None,
+            start_year,
+        });
+    }
+
+    // Ordered by user and movie, so the same files always give the same output.
+    let mut ratings = BTreeMap::<u32, BTreeMap<&str, u32>>::new();
+    let mut skipped = 0;
+    for record in read::<RatingRecord>(&input, RATINGS_FILE)? {
+        match imdb_ids.get(&record.movie_id) {
+            Some(id) if movies.contains_key(id) => {
+                let rating = ((record.rating * 2.).round() as u32).clamp(1, 10);
+                ratings
+                    .entry(record.user_id)
+                    .or_default()
+                    .insert(id.as_str(), rating);
+            }
+            _ => skipped += 1,
+        }
+    }
+
+    // Movies nobody rated don't help the engine.
+    let rated: HashSet<&str> = ratings.values().flat_map(|r| r.keys().copied()).collect();
+    let movies: Vec<ImdbTitle> = movies
+        .into_values()
+        .filter(|movie| rated.contains(movie.id.as_str()))
+        .collect();
+
+    let mut users: Vec<User> = ratings
+        .into_iter()
+        .map(|(id, ratings)| User {
+            name: format!("MovieLens {id}"),
+            ratings: ratings
+                .into_iter()
+                .map(|(movie, rating)| Rating::new(movie.to_string(), rating))
+                .collect(),
+        })
+        .collect();
+    let extra_users = users.split_off(shown_users.min(users.len()));
+
+    let data = Data {
+        movies,
+        users,
+        extra_users,
+    };
+    data.validate()?;
+    data.save(&output)?;
+
+    let rating_count: usize = data
+        .users
+        .iter()
+        .chain(&data.extra_users)
+        .map(|user| user.ratings.len())
+        .sum();
+    println!(
+        "Converted {rating_count} ratings of {} users and {} movies into {}",
+        data.users.len() + data.extra_users.len(),
+        data.movies.len(),
+        output.display()
+    );
+    if skipped > 0 {
+        println!("Skipped {skipped} ratings of movies without an IMDb id");
+    }
+    Ok(())
+}
@@ -0,0 +1,249 @@
+//! Content-based recommendations: movies similar to the ones the user liked,
+//! judged only by the movie details, so even movies nobody rated can be recommended.
+
+use std::collections::HashMap;
+
+use crate::{
+    ImdbTitle,
+    knn::NEIGHBOURS,
+    recommender::{RatingMatrix, Recommender, top},
+};
+
+/// How much similar plots count towards the similarity of two movies.
+const PLOT_WEIGHT: f32 = 0.5;
+/// How much sharing directors counts towards the similarity.
+const DIRECTORS_WEIGHT: f32 = 0.3;
+/// How much being released around the same time counts towards the similarity.
+const YEAR_WEIGHT: f32 = 0.2;
+/// Movies released this many years apart, or more, get nothing for the year.
+const YEAR_SPAN: f32 = 20.;
+
+/// Common English words which say nothing about the plot. Words shorter than 3 letters
+/// are skipped anyway.
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "his", "her", "their", "they", "them", "from", "into", "who",
+    "whom", "when", "while", "after", "before", "but", "are", "was", "were", "has", "have", "had",
+    "that", "this", "these", "those", "its", "out", "one", "two", "all", "about", "she", "him",
+    "not", "what", "which", "where", "will", "can", "more", "than", "then", "also",
+];
+
+/// Details of a movie the similarity is computed from.
+#[derive(Debug, Clone, Default)]
+struct Features {
+    /// TF-IDF weights of the words of the plot, normalized to a unit vector.
+    plot: HashMap<String, f32>,
+    directors: Vec<String>,
+    year: Option<u32>,
+}
+
+/// Splits a plot into lowercase words, without the [stop words](STOP_WORDS).
+fn words(plot: &str) -> impl Iterator<Item = String> {
+    plot.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+}
+
+impl Features {
+    /// Returns the features of all movies. The weight of a word in a plot is how many times
+    /// it appears there, times how rare it is among all plots.
+    fn all(movies: &[Option<&ImdbTitle>]) -> Vec<Features> {
+        let counts: Vec<HashMap<String, f32>> = movies
+            .iter()
+            .map(|movie| {
+                let mut counts = HashMap::new();
+                let plot = movie.and_then(|m| m.plot.as_deref()).unwrap_or_default();
+                for word in words(plot) {
+                    *counts.entry(word).or_default() += 1.;
+                }
+                counts
+            })
+            .collect();
+
+        let mut document_frequency = HashMap::<&str, f32>::new();
+        for word in counts.iter().flat_map(HashMap::keys) {
+            *document_frequency.entry(word).or_default() += 1.;
+        }
+        let n = movies.len() as f32;
+        let idf = |word: &str| ((1. + n) / (1. + document_frequency[word])).ln() + 1.;
+
+        movies
+            .iter()
+            .zip(&counts)
+            .map(|(movie, counts)| {
+                let mut plot: HashMap<String, f32> = counts
+                    .iter()
+                    .map(|(word, &count)| (word.clone(), count * idf(word)))
+                    .collect();
+                let norm = plot.values().map(|w| w * w).sum::<f32>().sqrt();
+                plot.values_mut().for_each(|w| *w /= norm.max(f32::EPSILON));
+                Features {
+                    plot,
+                    directors: movie
+                        .map(|m| m.directors.iter().map(|d| d.id.clone()).collect())
+                        .unwrap_or_default(),
+                    year: movie.and_then(|m| m.start_year),
+                }
+            })
+            .collect()
+    }
+
+    fn similarity(&self, other: &Features) -> f32 {
+        let plot: f32 = self
+            .plot
+            .iter()
+            .filter_map(|(word, w)| Some(w * other.plot.get(word)?))
+            .sum();
+        let shared = self
+            .directors
+            .iter()
+            .filter(|d| other.directors.contains(d))
+            .count();
+        let all = self.directors.len() + other.directors.len() - shared;
+        let directors = if all == 0 {
+            0.
+        } else {
+            shared as f32 / all as f32
+        };
+        let year = match (self.year, other.year) {
+            (Some(a), Some(b)) => (1. - a.abs_diff(b) as f32 / YEAR_SPAN).max(0.),
+            _ => 0.,
+        };
+        PLOT_WEIGHT * plot + DIRECTORS_WEIGHT * directors + YEAR_WEIGHT * year
+    }
+}
+
+/// Similarities are computed from the features when needed, rather than for all pairs up front,
+/// which would take quadratic time and memory on larger datasets.
+#[derive(Debug, Clone)]
+pub struct Content {
+    matrix: RatingMatrix,
+    /// Features of each movie, by index.
+    features: Vec<Features>,
+}
+
+impl Content {
+    pub fn new(ratings: &[(&str, &str, f32)], movies: &[ImdbTitle]) -> Content {
+        let mut matrix = RatingMatrix::new(ratings);
+        for movie in movies {
+            matrix.add_item(&movie.id);
+        }
+        let by_id: HashMap<&str, &ImdbTitle> = movies.iter().map(|m| (m.id.as_str(), m)).collect();
+        let details: Vec<_> = matrix
+            .items
+            .iter()
+            .map(|id| by_id.get(id.as_str()).copied())
+            .collect();
+        Content {
+            matrix,
+            features: Features::all(&details),
+        }
+    }
+
+    fn similarity(&self, a: usize, b: usize) -> f32 {
+        self.features[a].similarity(&self.features[b])
+    }
+
+    /// Returns the user's mean rating, and the rated movies most similar to the movie,
+    /// as `(index, similarity, deviation from the user's mean)`.
+    fn neighbours(&self, u: usize, i: usize) -> (f32, Vec<(usize, f32, f32)>) {
+        let rated = &self.matrix.by_user[u];
+        let mean = self.matrix.mean(rated);
+        let mut neighbours: Vec<_> = rated
+            .iter()
+            .filter(|&&(j, _)| j != i)
+            .map(|&(j, rating)| (j, self.similarity(i, j), rating - mean))
+            .filter(|&(_, similarity, _)| similarity > 0.)
+            .collect();
+        neighbours.sort_by(|a, b| b.1.total_cmp(&a.1));
+        neighbours.truncate(NEIGHBOURS);
+        (mean, neighbours)
+    }
+
+    pub(crate) fn matrix(&self) -> &RatingMatrix {
+        &self.matrix
+    }
+
+    pub(crate) fn predict_index(&self, u: usize, i: usize) -> f32 {
+        let (mean, neighbours) = self.neighbours(u, i);
+        let total: f32 = neighbours
+            .iter()
+            .map(|&(_, similarity, _)| similarity)
+            .sum();
+        if total == 0. {
+            return mean;
+        }
+        let adjustment: f32 = neighbours
+            .iter()
+            .map(|&(_, similarity, deviation)| similarity * deviation)
+            .sum();
+        mean + adjustment / total
+    }
+}
+
+impl Recommender for Content {
+    fn has_user(&self, user: &str) -> bool {
+        self.matrix.user(user).is_some()
+    }
+
+    fn predict(&self, user: &str, movie: &str) -> f32 {
+        match (self.matrix.user(user), self.matrix.item(movie)) {
+            (Some(u), Some(i)) => self.predict_index(u, i),
+            (Some(u), None) => self.matrix.mean(&self.matrix.by_user[u]),
+            _ => self.matrix.global_mean,
+        }
+    }
+
+    fn user_recs(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(u) = self.matrix.user(user) else {
+            return Vec::new();
+        };
+        self.matrix
+            .recommend(u, count, |i| self.predict_index(u, i))
+    }
+
+    fn item_recs(&self, movie: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(i) = self.matrix.item(movie) else {
+            return Vec::new();
+        };
+        let mut result: Vec<_> = (0..self.features.len())
+            .filter(|&j| j != i)
+            .map(|j| (self.matrix.items[j].as_str(), self.similarity(i, j)))
+            .collect();
+        top(&mut result, count);
+        result
+    }
+
+    fn item_similarity(&self, a: &str, b: &str) -> Option<f32> {
+        let (a, b) = (self.matrix.item(a)?, self.matrix.item(b)?);
+        Some(self.similarity(a, b))
+    }
+
+    /// The model only knows about movies, not about tastes of users.
+    fn similar_users(&self, _user: &str, _count: usize) -> Vec<(&str, f32)> {
+        Vec::new()
+    }
+
+    fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]) {
+        self.matrix.add_user(name, ratings);
+    }
+
+    fn explain(&self, user: &str, movie: &str) -> Vec<(&str, f32)> {
+        let (Some(u), Some(i)) = (self.matrix.user(user), self.matrix.item(movie)) else {
+            return Vec::new();
+        };
+        let (_, neighbours) = self.neighbours(u, i);
+        let total: f32 = neighbours
+            .iter()
+            .map(|&(_, similarity, _)| similarity)
+            .sum();
+        let mut contributions: Vec<_> = neighbours
+            .iter()
+            .map(|&(j, similarity, deviation)| {
+                let id = self.matrix.items[j].as_str();
+                (id, similarity * deviation / total)
+            })
+            .collect();
+        contributions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        contributions
+    }
+}
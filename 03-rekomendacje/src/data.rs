@@ -0,0 +1,244 @@
+//! Loading and validating the datasets produced by the `normalize` script.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, WrapErr, bail};
+use serde::de::DeserializeOwned;
+
+use crate::{ImdbTitle, User};
+
+const MOVIES_FILE: &str = "movies.json";
+const USERS_FILE: &str = "users.json";
+const EXTRA_USERS_FILE: &str = "extra_users.json";
+
+const MOVIES_STR: &str = include_str!(concat!(env!("REKOMENDACJE_DATA_DIR"), "/movies.json"));
+const USERS_STR: &str = include_str!(concat!(env!("REKOMENDACJE_DATA_DIR"), "/users.json"));
+const EXTRA_USERS_STR: &str =
+    include_str!(concat!(env!("REKOMENDACJE_DATA_DIR"), "/extra_users.json"));
+
+/// Ratings are given on a scale from 1 to 10.
+pub const RATING_RANGE: std::ops::RangeInclusive<u32> = 1..=10;
+
+/// At most this many problems are listed when the data doesn't validate.
+const MAX_REPORTED_PROBLEMS: usize = 20;
+
+/// All data the recommendation engine works on.
+#[derive(Debug, Clone)]
+pub struct Data {
+    pub movies: Vec<ImdbTitle>,
+    /// Users who can pick themselves in the program.
+    pub users: Vec<User>,
+    /// Users only used for training, not shown in the program.
+    pub extra_users: Vec<User>,
+}
+
+impl Data {
+    /// Returns the data embedded in the binary at compile time, from `REKOMENDACJE_DATA_DIR`.
+    pub fn embedded() -> Result<Self> {
+        let data = Data {
+            movies: parse(MOVIES_STR, MOVIES_FILE)?,
+            users: parse(USERS_STR, USERS_FILE)?,
+            extra_users: parse(EXTRA_USERS_STR, EXTRA_USERS_FILE)?,
+        };
+        data.validate().wrap_err("the embedded data is invalid")?;
+        Ok(data)
+    }
+
+    /// Loads the data from a directory with the same files as the output of `normalize`.
+    ///
+    /// `extra_users.json` is optional, the other files are required.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let read = |name: &str| -> Result<Option<(String, PathBuf)>> {
+            let path = dir.join(name);
+            match fs::read_to_string(&path) {
+                Ok(text) => Ok(Some((text, path))),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e).wrap_err_with(|| format!("failed to read {}", path.display())),
+            }
+        };
+        let required = |name: &str| -> Result<(String, PathBuf)> {
+            match read(name)? {
+                Some(file) => Ok(file),
+                None => bail!("{} is missing", dir.join(name).display()),
+            }
+        };
+
+        let (movies, movies_path) = required(MOVIES_FILE)?;
+        let (users, users_path) = required(USERS_FILE)?;
+        let extra_users = match read(EXTRA_USERS_FILE)? {
+            Some((text, path)) => parse(&text, &path.display().to_string())?,
+            None => Vec::new(),
+        };
+
+        let data = Data {
+            movies: parse(&movies, &movies_path.display().to_string())?,
+            users: parse(&users, &users_path.display().to_string())?,
+            extra_users,
+        };
+        data.validate()
+            .wrap_err_with(|| format!("the data in {} is invalid", dir.display()))?;
+        Ok(data)
+    }
+
+    /// Loads the data from `dir` if given, and the embedded data otherwise.
+    pub fn load_or_embedded(dir: Option<&Path>) -> Result<Self> {
+        match dir {
+            Some(dir) => Data::load(dir),
+            None => Data::embedded(),
+        }
+    }
+
+    /// Returns the movies by id, for quick lookups in large datasets.
+    pub fn movie_index(&self) -> HashMap<&str, &ImdbTitle> {
+        self.movies
+            .iter()
+            .map(|movie| (movie.id.as_str(), movie))
+            .collect()
+    }
+
+    /// Returns whether `dir` contains data which can be [loaded](Data::load).
+    pub fn exists(dir: &Path) -> bool {
+        dir.join(MOVIES_FILE).is_file()
+    }
+
+    /// Writes the data to a directory, in the same format as `normalize` does.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        let files = [
+            (MOVIES_FILE, serde_json::to_string(&self.movies)?),
+            (USERS_FILE, serde_json::to_string(&self.users)?),
+            (EXTRA_USERS_FILE, serde_json::to_string(&self.extra_users)?),
+        ];
+        for (name, text) in files {
+            let path = dir.join(name);
+            fs::write(&path, text)
+                .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Adds a user to the data in `dir`, or replaces the one with the same name.
+    /// A user who was only used for training is moved to the users shown in the list.
+    ///
+    /// If `dir` doesn't have any data yet, it's created from this data first.
+    pub fn save_user(&self, dir: &Path, user: &User) -> Result<()> {
+        let mut data = if Data::exists(dir) {
+            Data::load(dir)?
+        } else {
+            self.clone()
+        };
+        data.users.retain(|other| other.name != user.name);
+        data.extra_users.retain(|other| other.name != user.name);
+        data.users.push(user.clone());
+        data.validate()?;
+        data.save(dir)
+    }
+
+    /// Returns all ratings to train the engine on, as `(user, movie, rating)`,
+    /// ordered by movie and then by user, so the same data always gives the same list.
+    ///
+    /// Outliers are kept, it's up to each model whether to use them.
+    pub fn ratings(&self) -> Vec<(&str, &str, f32)> {
+        let mut movie_ratings = BTreeMap::<&str, Vec<(&str, f32)>>::new();
+        for user in self.users.iter().chain(&self.extra_users) {
+            for rating in &user.ratings {
+                movie_ratings
+                    .entry(&rating.movie)
+                    .or_default()
+                    .push((&user.name, rating.rating as f32));
+            }
+        }
+
+        let mut ratings = Vec::new();
+        for (movie, mut movie_ratings) in movie_ratings {
+            movie_ratings.sort_by(|a, b| a.0.cmp(b.0));
+            ratings.extend(
+                movie_ratings
+                    .into_iter()
+                    .map(|(user, rating)| (user, movie, rating)),
+            );
+        }
+        ratings
+    }
+
+    /// Checks the data for mistakes the JSON schema itself can't catch,
+    /// such as ratings of movies which are not in the movie list.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        let mut movie_ids = HashSet::new();
+        for (i, movie) in self.movies.iter().enumerate() {
+            if movie.id.trim().is_empty() {
+                problems.push(format!("{MOVIES_FILE}: movie #{} has an empty id", i + 1));
+            } else if !movie_ids.insert(movie.id.as_str()) {
+                problems.push(format!(
+                    "{MOVIES_FILE}: movie {:?} is listed twice",
+                    movie.id
+                ));
+            }
+        }
+
+        let mut names = HashMap::new();
+        let files = [
+            (USERS_FILE, &self.users),
+            (EXTRA_USERS_FILE, &self.extra_users),
+        ];
+        for (file, users) in files {
+            for user in users {
+                if user.name.trim().is_empty() {
+                    problems.push(format!("{file}: a user has an empty name"));
+                } else if let Some(other) = names.insert(user.name.as_str(), file) {
+                    problems.push(format!(
+                        "{file}: user {:?} is already listed in {other}",
+                        user.name
+                    ));
+                }
+
+                for rating in &user.ratings {
+                    if !movie_ids.contains(rating.movie.as_str()) {
+                        problems.push(format!(
+                            "{file}: {:?} rated {:?}, which is not in {MOVIES_FILE}",
+                            user.name, rating.movie
+                        ));
+                    }
+                    if !RATING_RANGE.contains(&rating.rating) {
+                        problems.push(format!(
+                            "{file}: {:?} rated {:?} {}, outside of the {}-{} scale",
+                            user.name,
+                            rating.movie,
+                            rating.rating,
+                            RATING_RANGE.start(),
+                            RATING_RANGE.end(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let mut report = String::new();
+        for problem in problems.iter().take(MAX_REPORTED_PROBLEMS) {
+            writeln!(report, "  - {problem}").ok();
+        }
+        if problems.len() > MAX_REPORTED_PROBLEMS {
+            writeln!(
+                report,
+                "  ... and {} more",
+                problems.len() - MAX_REPORTED_PROBLEMS
+            )
+            .ok();
+        }
+        bail!("found {} problems:\n{report}", problems.len())
+    }
+}
+
+fn parse<T: DeserializeOwned>(text: &str, file: &str) -> Result<T> {
+    serde_json::from_str(text).wrap_err_with(|| format!("{file} is not in the expected format"))
+}
@@ -0,0 +1,105 @@
+//! Re-ranking of recommendations, so the top of the list isn't a single franchise or director.
+//!
+//! Movies are picked one at a time with maximal marginal relevance: each pick balances
+//! how good the movie is against how similar it is to the movies picked before.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{ImdbTitle, recommender::Recommender};
+
+/// Diversity of the recommendations unless set otherwise, see [`Diversity::rerank`].
+pub const DEFAULT_DIVERSITY: f32 = 0.4;
+
+/// How much the similarity of two movies depends on the model, the rest comes from
+/// their common directors.
+const MODEL_WEIGHT: f32 = 0.5;
+
+/// Only this many of the best movies are considered for each pick, so a diverse list
+/// doesn't reach for movies the user wouldn't like.
+const CANDIDATES: usize = 100;
+
+/// Similarity of movies, from the model and from their directors.
+pub struct Diversity<'a> {
+    engine: &'a dyn Recommender,
+    directors: HashMap<&'a str, HashSet<&'a str>>,
+}
+
+impl<'a> Diversity<'a> {
+    pub fn new(engine: &'a dyn Recommender, movies: &'a [ImdbTitle]) -> Self {
+        let directors = movies
+            .iter()
+            .map(|movie| {
+                let directors = movie.directors.iter().map(|d| d.id.as_str()).collect();
+                (movie.id.as_str(), directors)
+            })
+            .collect();
+        Diversity { engine, directors }
+    }
+
+    /// Returns how similar two movies are, from 0 to 1. Movies which the model considers
+    /// opposites count as not similar at all.
+    pub fn similarity(&self, a: &str, b: &str) -> f32 {
+        let model = self.engine.item_similarity(a, b).unwrap_or(0.).max(0.);
+        let directors = match (self.directors.get(a), self.directors.get(b)) {
+            (Some(a), Some(b)) if !a.is_empty() && !b.is_empty() => {
+                a.intersection(b).count() as f32 / a.union(b).count() as f32
+            }
+            _ => 0.,
+        };
+        MODEL_WEIGHT * model + (1. - MODEL_WEIGHT) * directors
+    }
+
+    /// Picks `count` movies from `ranked`, which is ordered from the best one, with a score
+    /// where higher is better. `diversity` goes from 0, which keeps the order, to 1,
+    /// which only cares about the movies being different.
+    pub fn rerank<'b>(
+        &self,
+        ranked: &[(&'b str, f32)],
+        diversity: f32,
+        count: usize,
+    ) -> Vec<(&'b str, f32)> {
+        if diversity <= 0. {
+            return ranked.iter().copied().take(count).collect();
+        }
+        let mut candidates: Vec<_> = ranked.iter().copied().take(CANDIDATES).collect();
+        // Scores are scaled to 0-1, the same range as the similarities.
+        let (min, max) = candidates.iter().fold(
+            (f32::INFINITY, f32::NEG_INFINITY),
+            |(min, max), &(_, score)| (min.min(score), max.max(score)),
+        );
+        let relevance = |score: f32| (score - min) / (max - min).max(f32::EPSILON);
+
+        let mut picked: Vec<(&str, f32)> = Vec::new();
+        // The highest similarity of each candidate to any picked movie.
+        let mut redundancy = vec![0f32; candidates.len()];
+        while picked.len() < count && !candidates.is_empty() {
+            let value = |i: usize| {
+                (1. - diversity) * relevance(candidates[i].1) - diversity * redundancy[i]
+            };
+            let best = (0..candidates.len())
+                .max_by(|&a, &b| value(a).total_cmp(&value(b)).then(b.cmp(&a)))
+                .unwrap();
+            let movie = candidates.remove(best);
+            redundancy.remove(best);
+            for (&(other, _), redundancy) in candidates.iter().zip(&mut redundancy) {
+                *redundancy = redundancy.max(self.similarity(movie.0, other));
+            }
+            picked.push(movie);
+        }
+        picked
+    }
+
+    /// Returns the mean dissimilarity of all pairs of the movies, from 0 when they're all
+    /// the same, to 1 when they have nothing in common.
+    pub fn intra_list(&self, movies: &[&str]) -> f32 {
+        let mut total = 0.;
+        let mut pairs = 0;
+        for (i, a) in movies.iter().enumerate() {
+            for b in &movies[i + 1..] {
+                total += 1. - self.similarity(a, b);
+                pairs += 1;
+            }
+        }
+        if pairs == 0 { 0. } else { total / pairs as f32 }
+    }
+}
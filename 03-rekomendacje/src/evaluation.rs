@@ -0,0 +1,195 @@
+//! Offline evaluation of the recommendation engine: the ratings are split into a training
+//! and a test set, and the model trained on the first one is scored on the second one.
+
+use std::collections::{HashMap, HashSet};
+
+use rand::{SeedableRng, rngs::SmallRng, seq::SliceRandom};
+
+use crate::{ImdbTitle, diversity::Diversity, recommender::Recommender};
+
+/// Ratings from this one up count as the user liking the movie,
+/// for the metrics which only care whether a recommendation is relevant.
+pub const RELEVANT_RATING: f32 = 7.;
+
+/// A rating, as `(user, movie, rating)`.
+pub type Rating<'a> = (&'a str, &'a str, f32);
+
+/// How the ratings are split into training and test sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// The ratings are split into `k` equal parts, each of them is the test set once.
+    KFold(usize),
+    /// One rating of every user with at least two ratings is left out for the test set.
+    LeaveOneOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fold<'a> {
+    pub train: Vec<Rating<'a>>,
+    pub test: Vec<Rating<'a>>,
+}
+
+/// Splits the ratings into folds. The same seed always gives the same folds.
+pub fn split<'a>(ratings: &[Rating<'a>], split: Split, seed: u64) -> Vec<Fold<'a>> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    match split {
+        Split::KFold(k) => {
+            let mut shuffled = ratings.to_vec();
+            shuffled.shuffle(&mut rng);
+            (0..k)
+                .map(|fold| {
+                    let (test, train) = shuffled
+                        .iter()
+                        .enumerate()
+                        .partition::<Vec<_>, _>(|(i, _)| i % k == fold);
+                    Fold {
+                        train: train.into_iter().map(|(_, &r)| r).collect(),
+                        test: test.into_iter().map(|(_, &r)| r).collect(),
+                    }
+                })
+                .collect()
+        }
+        Split::LeaveOneOut => {
+            let mut by_user = HashMap::<&str, Vec<Rating<'a>>>::new();
+            for &rating in ratings {
+                by_user.entry(rating.0).or_default().push(rating);
+            }
+            let mut users: Vec<_> = by_user.into_values().collect();
+            // Hash maps aren't ordered, so the users are sorted to keep the split deterministic.
+            users.sort_by(|a, b| a[0].0.cmp(b[0].0));
+
+            let mut fold = Fold {
+                train: Vec::new(),
+                test: Vec::new(),
+            };
+            for mut user_ratings in users {
+                if user_ratings.len() >= 2 {
+                    user_ratings.shuffle(&mut rng);
+                    fold.test.extend(user_ratings.pop());
+                }
+                fold.train.extend(user_ratings);
+            }
+            vec![fold]
+        }
+    }
+}
+
+/// Scores of a model on a test set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Metrics {
+    /// Root mean squared error of the predicted ratings.
+    pub rmse: f32,
+    /// Mean absolute error of the predicted ratings.
+    pub mae: f32,
+    /// Part of the top `k` recommendations which the user liked, see [`RELEVANT_RATING`].
+    pub precision: f32,
+    /// Part of the movies the user liked which made it into the top `k` recommendations.
+    pub recall: f32,
+    /// Normalized discounted cumulative gain of the top `k` recommendations,
+    /// which also rewards putting the liked movies higher.
+    pub ndcg: f32,
+    /// Mean dissimilarity of the top `k` recommendations to each other,
+    /// see [`Diversity::intra_list`].
+    pub diversity: f32,
+    /// Mean self-information of the top `k` recommendations, `-log₂` of the part of users
+    /// who rated the movie in the training set. Higher means less obvious recommendations.
+    pub novelty: f32,
+}
+
+impl Metrics {
+    /// Scores the model, trained on `fold.train`, on the ratings it wasn't trained on.
+    /// The recommendations are re-ranked with the given `diversity`, see [`Diversity::rerank`].
+    ///
+    /// The ranking metrics only count users who liked at least one movie from the test set.
+    pub fn evaluate(
+        model: &dyn Recommender,
+        movies: &[ImdbTitle],
+        fold: &Fold,
+        k: usize,
+        diversity: f32,
+    ) -> Metrics {
+        let test = &fold.test;
+        if test.is_empty() {
+            return Metrics::default();
+        }
+
+        let mut squared_error = 0.;
+        let mut absolute_error = 0.;
+        let mut liked = HashMap::<&str, Vec<&str>>::new();
+        for &(user, movie, rating) in test {
+            let error = model.predict(user, movie) - rating;
+            squared_error += error * error;
+            absolute_error += error.abs();
+            if rating >= RELEVANT_RATING {
+                liked.entry(user).or_default().push(movie);
+            }
+        }
+
+        let mut metrics = Metrics {
+            rmse: (squared_error / test.len() as f32).sqrt(),
+            mae: absolute_error / test.len() as f32,
+            ..Default::default()
+        };
+        if liked.is_empty() || k == 0 {
+            return metrics;
+        }
+
+        let mut raters = HashMap::<&str, usize>::new();
+        let mut train_users = HashSet::new();
+        for &(user, movie, _) in &fold.train {
+            *raters.entry(movie).or_default() += 1;
+            train_users.insert(user);
+        }
+        // Smoothed, so movies nobody rated don't have infinite novelty.
+        let self_information = |movie: &str| {
+            let raters = raters.get(movie).copied().unwrap_or(0);
+            -((raters + 1) as f32 / (train_users.len() + 1) as f32).log2()
+        };
+        let reranker = Diversity::new(model, movies);
+
+        // Gain of a hit at each position, discounted by how far down the list it is.
+        let gain = |position: usize| 1. / (position as f32 + 2.).log2();
+        for (user, liked_movies) in &liked {
+            let recs = reranker.rerank(&model.user_recs(user, usize::MAX), diversity, k);
+            let ids: Vec<_> = recs.iter().map(|&(movie, _)| movie).collect();
+            metrics.diversity += reranker.intra_list(&ids);
+            metrics.novelty += ids.iter().map(|movie| self_information(movie)).sum::<f32>()
+                / ids.len().max(1) as f32;
+            let mut hits = 0;
+            let mut dcg = 0.;
+            for (position, (movie, _)) in recs.iter().enumerate() {
+                if liked_movies.contains(movie) {
+                    hits += 1;
+                    dcg += gain(position);
+                }
+            }
+            let ideal: f32 = (0..liked_movies.len().min(k)).map(gain).sum();
+
+            metrics.precision += hits as f32 / k as f32;
+            metrics.recall += hits as f32 / liked_movies.len() as f32;
+            metrics.ndcg += dcg / ideal;
+        }
+        let users = liked.len() as f32;
+        metrics.precision /= users;
+        metrics.recall /= users;
+        metrics.ndcg /= users;
+        metrics.diversity /= users;
+        metrics.novelty /= users;
+        metrics
+    }
+
+    /// Returns the mean of the scores, e.g. from all folds.
+    pub fn mean(all: &[Metrics]) -> Metrics {
+        let n = all.len().max(1) as f32;
+        let sum = |f: fn(&Metrics) -> f32| all.iter().map(f).sum::<f32>() / n;
+        Metrics {
+            rmse: sum(|m| m.rmse),
+            mae: sum(|m| m.mae),
+            precision: sum(|m| m.precision),
+            recall: sum(|m| m.recall),
+            ndcg: sum(|m| m.ndcg),
+            diversity: sum(|m| m.diversity),
+            novelty: sum(|m| m.novelty),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! The `export` subcommand, writing recommendations to a file without starting the interface.
+
+use std::{fmt::Write as _, path::PathBuf, process::exit};
+
+use color_eyre::eyre::{Result, bail};
+use i18n::{t, tr};
+use rekomendacje::{data::Data, recommender::Recommender};
+use serde::Serialize;
+
+/// Catalog keys of the usage line, and of the notes printed below it.
+const USAGE: &str = "usage.export";
+const USAGE_NOTES: [&str; 3] = [
+    "usage.export.users",
+    "usage.export.format",
+    "usage.export.output",
+];
+
+/// How many movies are exported for each user, unless `--count` says otherwise.
+const DEFAULT_COUNT: usize = 10;
+
+fn bail_usage() -> ! {
+    eprintln!("{}", t(USAGE));
+    eprintln!();
+    for key in USAGE_NOTES {
+        eprintln!("{}", t(key));
+    }
+    exit(1);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl Format {
+    fn from_extension(extension: &str) -> Option<Format> {
+        match extension {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// What to export, parsed from the arguments following `export`.
+#[derive(Debug, Clone)]
+pub struct Options {
+    anti: bool,
+    /// Users to export, or all visible users if empty.
+    users: Vec<String>,
+    count: usize,
+    format: Format,
+    output: Option<PathBuf>,
+}
+
+impl Options {
+    /// Parses the arguments, exiting with the usage if they're invalid.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Options {
+        let anti = match args.next().as_deref() {
+            Some("recs") => false,
+            Some("anti-recs") => true,
+            _ => bail_usage(),
+        };
+        let mut users = Vec::new();
+        let mut count = DEFAULT_COUNT;
+        let mut format = None;
+        let mut output = None::<PathBuf>;
+        while let Some(arg) = args.next() {
+            let value = args.next().unwrap_or_else(|| bail_usage());
+            match arg.as_str() {
+                "--user" => users.push(value),
+                "--count" => count = value.parse().unwrap_or_else(|_| bail_usage()),
+                "--format" => {
+                    format = Some(Format::from_extension(&value).unwrap_or_else(|| bail_usage()))
+                }
+                "--output" => output = Some(value.into()),
+                _ => bail_usage(),
+            }
+        }
+        let format = format
+            .or_else(|| {
+                let extension = output.as_ref()?.extension()?.to_str()?;
+                Format::from_extension(extension)
+            })
+            .unwrap_or(Format::Markdown);
+        Options {
+            anti,
+            users,
+            count,
+            format,
+            output,
+        }
+    }
+}
+
+/// One recommended movie.
+#[derive(Debug, Serialize)]
+struct Row<'a> {
+    user: &'a str,
+    rank: usize,
+    movie: &'a str,
+    title: &'a str,
+    year: Option<u32>,
+    directors: Vec<&'a str>,
+    prediction: f32,
+}
+
+/// Writes the recommendations as given in the options.
+pub fn run(options: &Options, data: &Data, engine: &dyn Recommender) -> Result<()> {
+    let users: Vec<&str> = if options.users.is_empty() {
+        data.users.iter().map(|user| user.name.as_str()).collect()
+    } else {
+        options.users.iter().map(String::as_str).collect()
+    };
+
+    let movies = data.movie_index();
+    let mut rows = Vec::new();
+    for user in users {
+        if !engine.has_user(user) {
+            bail!(tr!("usage.unknown_user", user = format!("{user:?}")));
+        }
+        let recs = if options.anti {
+            let mut recs = engine.user_recs(user, usize::MAX);
+            recs.reverse();
+            recs.truncate(options.count);
+            recs
+        } else {
+            engine.user_recs(user, options.count)
+        };
+        for (rank, (id, prediction)) in recs.into_iter().enumerate() {
+            let Some(movie) = movies.get(id) else {
+                continue;
+            };
+            rows.push(Row {
+                user,
+                rank: rank + 1,
+                movie: &movie.id,
+                title: &movie.primary_title,
+                year: movie.start_year,
+                directors: movie
+                    .directors
+                    .iter()
+                    .map(|director| director.display_name.as_str())
+                    .collect(),
+                prediction,
+            });
+        }
+    }
+
+    let text = match options.format {
+        Format::Json => serde_json::to_string_pretty(&rows)? + "\n",
+        Format::Csv => csv(&rows),
+        Format::Markdown => markdown(&rows),
+    };
+    match &options.output {
+        Some(path) => std::fs::write(path, text)?,
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+fn csv(rows: &[Row]) -> String {
+    // Quotes are only needed around fields with commas, quotes or line breaks,
+    // but quoting every text field is just as valid and simpler.
+    let quote = |field: &str| format!("\"{}\"", field.replace('"', "\"\""));
+    let mut text = String::from("user,rank,movie,title,year,directors,prediction\n");
+    for row in rows {
+        let year = row.year.map(|year| year.to_string()).unwrap_or_default();
+        writeln!(
+            text,
+            "{},{},{},{},{year},{},{:.2}",
+            quote(row.user),
+            row.rank,
+            row.movie,
+            quote(row.title),
+            quote(&row.directors.join(", ")),
+            row.prediction,
+        )
+        .unwrap();
+    }
+    text
+}
+
+/// Returns a table for each user, with headers in the language of the interface.
+fn markdown(rows: &[Row]) -> String {
+    // Pipes would end a table cell early.
+    let escape = |text: &str| text.replace('|', "\\|");
+    let mut text = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        if i == 0 || rows[i - 1].user != row.user {
+            if i > 0 {
+                text.push('\n');
+            }
+            writeln!(text, "## {}\n", escape(row.user)).unwrap();
+            writeln!(
+                text,
+                "| # | {} | {} | {} | {} |",
+                t("export.title"),
+                t("export.year"),
+                t("export.directors"),
+                t("export.prediction"),
+            )
+            .unwrap();
+            text.push_str("|--:|---|--:|---|--:|\n");
+        }
+        let year = row.year.map(|year| year.to_string()).unwrap_or_default();
+        writeln!(
+            text,
+            "| {} | {} | {year} | {} | {:.1} |",
+            row.rank,
+            escape(row.title),
+            escape(&row.directors.join(", ")),
+            row.prediction,
+        )
+        .unwrap();
+    }
+    text
+}
@@ -0,0 +1,122 @@
+//! What users told the program about their recommendations: ratings of recommended movies,
+//! movies they're not interested in, and movies they want to watch.
+//!
+//! The feedback is kept in a file next to the user data, and the ratings are merged into
+//! the data on the next run, so the model is trained on them.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Rating,
+    data::{Data, RATING_RANGE},
+};
+
+/// Feedback of all users, by name.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Feedback {
+    pub users: BTreeMap<String, UserFeedback>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFeedback {
+    /// Ratings of recommended movies, by movie id.
+    #[serde(default)]
+    pub ratings: BTreeMap<String, u32>,
+    /// Movies which aren't recommended to the user anymore.
+    #[serde(default)]
+    pub not_interested: BTreeSet<String>,
+    #[serde(default)]
+    pub watchlist: BTreeSet<String>,
+}
+
+impl Feedback {
+    /// Loads the feedback from `path`, or returns empty feedback if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .wrap_err_with(|| format!("{} is not in the expected format", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Feedback::default()),
+            Err(e) => Err(e).wrap_err_with(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Returns the feedback of the user, empty if they didn't give any.
+    pub fn user(&self, name: &str) -> &UserFeedback {
+        static EMPTY: UserFeedback = UserFeedback {
+            ratings: BTreeMap::new(),
+            not_interested: BTreeSet::new(),
+            watchlist: BTreeSet::new(),
+        };
+        self.users.get(name).unwrap_or(&EMPTY)
+    }
+
+    pub fn user_mut(&mut self, name: &str) -> &mut UserFeedback {
+        self.users.entry(name.to_string()).or_default()
+    }
+
+    /// Adds the ratings to the users of `data`, replacing their earlier ratings of the same
+    /// movies. Feedback of users or movies which aren't in the data, and ratings outside
+    /// of [`RATING_RANGE`], are skipped.
+    pub fn merge_into(&self, data: &mut Data) {
+        let movies: BTreeSet<&str> = data.movies.iter().map(|m| m.id.as_str()).collect();
+        for user in data.users.iter_mut().chain(&mut data.extra_users) {
+            let Some(feedback) = self.users.get(&user.name) else {
+                continue;
+            };
+            for (movie, &rating) in &feedback.ratings {
+                if !movies.contains(movie.as_str()) || !RATING_RANGE.contains(&rating) {
+                    continue;
+                }
+                match user.ratings.iter_mut().find(|r| r.movie == *movie) {
+                    Some(existing) => existing.rating = rating,
+                    None => user.ratings.push(Rating::new(movie.clone(), rating)),
+                }
+            }
+        }
+    }
+}
+
+impl UserFeedback {
+    /// Rates the movie, which also takes it off the watchlist.
+    pub fn rate(&mut self, movie: &str, rating: u32) {
+        self.ratings.insert(movie.to_string(), rating);
+        self.watchlist.remove(movie);
+    }
+
+    /// Marks the movie as not interesting, or takes the mark back.
+    /// Returns whether the movie is marked now.
+    pub fn toggle_not_interested(&mut self, movie: &str) -> bool {
+        toggle(&mut self.not_interested, movie)
+    }
+
+    /// Adds the movie to the watchlist, or takes it off.
+    /// Returns whether the movie is on the watchlist now.
+    pub fn toggle_watchlist(&mut self, movie: &str) -> bool {
+        toggle(&mut self.watchlist, movie)
+    }
+}
+
+fn toggle(set: &mut BTreeSet<String>, movie: &str) -> bool {
+    if set.remove(movie) {
+        false
+    } else {
+        set.insert(movie.to_string());
+        true
+    }
+}
@@ -0,0 +1,137 @@
+//! Narrowing down recommendations by release year, director and title.
+//!
+//! Filters are typed as a single line of space separated parts:
+//! - `2010-`, `-1999`, `1990-2000` or `1994` - release years,
+//! - `@name` - a director, matched loosely, so `@kubrik` finds Stanley Kubrick,
+//! - anything else - a part of the title, also matched loosely.
+
+use std::collections::HashSet;
+
+use strsim::normalized_damerau_levenshtein;
+
+use crate::{ImdbTitle, User};
+
+/// How similar, from 0 to 1, a part of the title or name has to be to the searched text.
+const FUZZY_THRESHOLD: f64 = 0.75;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// The text the filter was parsed from.
+    pub query: String,
+    pub min_year: Option<u32>,
+    pub max_year: Option<u32>,
+    pub director: Option<String>,
+    pub title: Option<String>,
+    /// Leaves out movies by directors of any movie the user rated.
+    pub new_directors: bool,
+}
+
+impl Filter {
+    /// Parses a filter, keeping [`new_directors`](Filter::new_directors) off.
+    /// Parts which aren't years or directors are searched for in the title.
+    pub fn parse(query: &str) -> Filter {
+        let mut filter = Filter {
+            query: query.trim().to_string(),
+            ..Filter::default()
+        };
+        let mut title = Vec::new();
+        for part in query.split_whitespace() {
+            if let Some(director) = part.strip_prefix('@') {
+                if !director.is_empty() {
+                    filter.director = Some(director.to_string());
+                }
+            } else if let Some((min, max)) = years(part) {
+                (filter.min_year, filter.max_year) = (min, max);
+            } else {
+                title.push(part);
+            }
+        }
+        if !title.is_empty() {
+            filter.title = Some(title.join(" "));
+        }
+        filter
+    }
+
+    /// Returns a predicate for the movies which pass the filter for the user.
+    /// Movies of unknown year don't pass a filter by year.
+    pub fn matcher<'a>(
+        &'a self,
+        user: &User,
+        movies: &'a [ImdbTitle],
+    ) -> impl Fn(&ImdbTitle) -> bool + 'a {
+        let rated_directors: HashSet<&str> = if self.new_directors {
+            let rated: HashSet<&str> = user.ratings.iter().map(|r| r.movie.as_str()).collect();
+            movies
+                .iter()
+                .filter(|movie| rated.contains(movie.id.as_str()))
+                .flat_map(|movie| &movie.directors)
+                .map(|director| director.id.as_str())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        move |movie| {
+            let year_matches = |bound: Option<u32>, ok: fn(u32, u32) -> bool| match bound {
+                None => true,
+                Some(bound) => movie.start_year.is_some_and(|year| ok(year, bound)),
+            };
+            year_matches(self.min_year, |year, min| year >= min)
+                && year_matches(self.max_year, |year, max| year <= max)
+                && self.director.as_ref().is_none_or(|director| {
+                    movie
+                        .directors
+                        .iter()
+                        .any(|d| fuzzy_contains(&d.display_name, director))
+                })
+                && self.title.as_ref().is_none_or(|title| {
+                    fuzzy_contains(&movie.primary_title, title)
+                        || movie
+                            .original_title
+                            .as_ref()
+                            .is_some_and(|original| fuzzy_contains(original, title))
+                })
+                && !movie
+                    .directors
+                    .iter()
+                    .any(|director| rated_directors.contains(director.id.as_str()))
+        }
+    }
+}
+
+/// Parses a year range, where either end may be left out.
+fn years(part: &str) -> Option<(Option<u32>, Option<u32>)> {
+    if !part.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let year = |text: &str| -> Option<Option<u32>> {
+        if text.is_empty() {
+            Some(None)
+        } else {
+            text.parse().ok().map(Some)
+        }
+    };
+    match part.split_once('-') {
+        Some((min, max)) => Some((year(min)?, year(max)?)),
+        None => {
+            let year = part.parse().ok()?;
+            Some((Some(year), Some(year)))
+        }
+    }
+}
+
+/// Returns whether `text` contains `needle`, ignoring case, or a run of words
+/// similar enough to it, which allows for typos and missing diacritics.
+fn fuzzy_contains(text: &str, needle: &str) -> bool {
+    let (text, needle) = (text.to_lowercase(), needle.to_lowercase());
+    if text.contains(&needle) {
+        return true;
+    }
+    let words: Vec<_> = text.split_whitespace().collect();
+    let length = needle
+        .split_whitespace()
+        .count()
+        .clamp(1, words.len().max(1));
+    words
+        .windows(length)
+        .any(|window| normalized_damerau_levenshtein(&window.join(" "), &needle) >= FUZZY_THRESHOLD)
+}
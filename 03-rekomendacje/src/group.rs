@@ -0,0 +1,166 @@
+//! Recommendations for a group of users picking a movie to watch together.
+
+use std::collections::HashMap;
+
+use i18n::t;
+
+use crate::recommender::Recommender;
+
+/// How the predicted ratings of the members are combined into a score for the whole group.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// The mean of the predicted ratings.
+    #[default]
+    Average,
+    /// The lowest predicted rating, so nobody has to sit through a movie they'd hate.
+    LeastMisery,
+    /// The highest predicted rating, so at least someone loves the movie.
+    MostPleasure,
+    /// Movies are picked one at a time, each time favouring the members
+    /// who liked the movies picked so far the least.
+    Fairness,
+}
+
+impl Aggregation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregation::Average => t("group.average"),
+            Aggregation::LeastMisery => t("group.least_misery"),
+            Aggregation::MostPleasure => t("group.most_pleasure"),
+            Aggregation::Fairness => t("group.fairness"),
+        }
+    }
+
+    /// Returns the next strategy, going back to the first one after the last.
+    pub fn next(self) -> Self {
+        match self {
+            Aggregation::Average => Aggregation::LeastMisery,
+            Aggregation::LeastMisery => Aggregation::MostPleasure,
+            Aggregation::MostPleasure => Aggregation::Fairness,
+            Aggregation::Fairness => Aggregation::Average,
+        }
+    }
+}
+
+/// A movie recommended to a group.
+#[derive(Debug, Clone)]
+pub struct GroupRec<'a> {
+    pub movie: &'a str,
+    /// Score of the movie for the whole group. With [`Aggregation::Fairness`],
+    /// the order doesn't follow the score, which is then the mean of the predicted ratings.
+    pub score: f32,
+    /// Predicted rating of each member, in the same order as the members.
+    pub predictions: Vec<f32>,
+}
+
+/// Returns up to `count` movies for the group, from the best one.
+///
+/// Movies rated by any of the members are left out.
+pub fn group_recs<'a>(
+    engine: &'a dyn Recommender,
+    members: &[&str],
+    aggregation: Aggregation,
+    count: usize,
+) -> Vec<GroupRec<'a>> {
+    let Some((first, rest)) = members.split_first() else {
+        return Vec::new();
+    };
+    // Recommendations of a user never include movies they rated, so only movies
+    // recommended to everyone are left unrated by all members.
+    let mut predictions: HashMap<&str, Vec<f32>> = engine
+        .user_recs(first, usize::MAX)
+        .into_iter()
+        .map(|(movie, prediction)| (movie, vec![prediction]))
+        .collect();
+    for member in rest {
+        let recs: HashMap<_, _> = engine.user_recs(member, usize::MAX).into_iter().collect();
+        predictions.retain(|movie, predictions| match recs.get(movie) {
+            Some(&prediction) => {
+                predictions.push(prediction);
+                true
+            }
+            None => false,
+        });
+    }
+    let mut candidates: Vec<_> = predictions.into_iter().collect();
+    // Hash maps aren't ordered, so ties would come out in a random order.
+    candidates.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mean = |predictions: &[f32]| predictions.iter().sum::<f32>() / predictions.len() as f32;
+    let score = |predictions: &[f32]| match aggregation {
+        Aggregation::Average | Aggregation::Fairness => mean(predictions),
+        Aggregation::LeastMisery => predictions.iter().copied().fold(f32::INFINITY, f32::min),
+        Aggregation::MostPleasure => predictions
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max),
+    };
+
+    if aggregation == Aggregation::Fairness {
+        return fair(candidates, members.len(), count)
+            .into_iter()
+            .map(|(movie, predictions)| GroupRec {
+                movie,
+                score: mean(&predictions),
+                predictions,
+            })
+            .collect();
+    }
+
+    let mut recs: Vec<_> = candidates
+        .into_iter()
+        .map(|(movie, predictions)| GroupRec {
+            movie,
+            score: score(&predictions),
+            predictions,
+        })
+        .collect();
+    recs.sort_by(|a, b| b.score.total_cmp(&a.score));
+    recs.truncate(count);
+    recs
+}
+
+/// Picks movies one at a time. Each member's predictions are scaled to 0-1, so harsh and
+/// generous raters count the same, and the picked movie is the one with the best weighted mean,
+/// where members get less weight the more they liked the movies picked before.
+fn fair(
+    mut candidates: Vec<(&str, Vec<f32>)>,
+    members: usize,
+    count: usize,
+) -> Vec<(&str, Vec<f32>)> {
+    let range = |member: usize| {
+        candidates
+            .iter()
+            .map(|(_, predictions)| predictions[member])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+                (min.min(p), max.max(p))
+            })
+    };
+    let ranges: Vec<_> = (0..members).map(range).collect();
+    let scaled = |predictions: &[f32], member: usize| {
+        let (min, max) = ranges[member];
+        (predictions[member] - min) / (max - min).max(f32::EPSILON)
+    };
+
+    let mut satisfaction = vec![0.; members];
+    let mut picked = Vec::new();
+    while picked.len() < count && !candidates.is_empty() {
+        let weights: Vec<f32> = satisfaction.iter().map(|s| 1. / (1. + s)).collect();
+        let total: f32 = weights.iter().sum();
+        let score = |predictions: &[f32]| {
+            (0..members)
+                .map(|member| weights[member] * scaled(predictions, member))
+                .sum::<f32>()
+                / total
+        };
+        let best = (0..candidates.len())
+            .max_by(|&a, &b| score(&candidates[a].1).total_cmp(&score(&candidates[b].1)))
+            .unwrap();
+        let (movie, predictions) = candidates.swap_remove(best);
+        for (member, satisfaction) in satisfaction.iter_mut().enumerate() {
+            *satisfaction += scaled(&predictions, member);
+        }
+        picked.push((movie, predictions));
+    }
+    picked
+}
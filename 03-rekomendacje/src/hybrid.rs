@@ -0,0 +1,132 @@
+//! Hybrid recommendations: a blend of matrix factorization and the content-based model,
+//! leaning on the content for movies with few ratings, which matrix factorization knows little
+//! about, or doesn't know at all.
+
+use std::collections::HashMap;
+
+use crate::{
+    content::Content,
+    model::Model,
+    recommender::{Recommender, top},
+};
+
+/// A movie with this many ratings is scored half by matrix factorization and half by content.
+/// Movies with more ratings lean towards matrix factorization, with fewer - towards content.
+const CONTENT_RATINGS: f32 = 5.;
+
+#[derive(Debug, Clone)]
+pub struct Hybrid {
+    collaborative: Model,
+    content: Content,
+}
+
+impl Hybrid {
+    /// Combines models trained on the same ratings.
+    pub fn new(collaborative: Model, content: Content) -> Hybrid {
+        Hybrid {
+            collaborative,
+            content,
+        }
+    }
+
+    /// Returns how much matrix factorization counts for the movie, from 0 to 1.
+    fn weight(&self, i: usize) -> f32 {
+        let ratings = self.content.matrix().by_item[i].len() as f32;
+        ratings / (ratings + CONTENT_RATINGS)
+    }
+
+    fn blend(&self, i: usize, collaborative: f32, content: f32) -> f32 {
+        let weight = self.weight(i);
+        weight * collaborative + (1. - weight) * content
+    }
+
+    fn predict_index(&self, user: &str, u: usize, i: usize) -> f32 {
+        let movie = &self.content.matrix().items[i];
+        self.blend(
+            i,
+            self.collaborative.predict(user, movie),
+            self.content.predict_index(u, i),
+        )
+    }
+
+    /// Blends the similarities of two movies, counting matrix factorization
+    /// only as much as the movie with fewer ratings allows.
+    fn similarity_index(&self, a: usize, b: usize) -> f32 {
+        let items = &self.content.matrix().items;
+        let (a_id, b_id) = (&items[a], &items[b]);
+        let content = self.content.item_similarity(a_id, b_id).unwrap_or(0.);
+        let Some(collaborative) = self.collaborative.item_similarity(a_id, b_id) else {
+            return content;
+        };
+        let weight = self.weight(a).min(self.weight(b));
+        weight * collaborative + (1. - weight) * content
+    }
+}
+
+impl Recommender for Hybrid {
+    fn has_user(&self, user: &str) -> bool {
+        self.content.has_user(user)
+    }
+
+    fn predict(&self, user: &str, movie: &str) -> f32 {
+        let m = self.content.matrix();
+        match (m.user(user), m.item(movie)) {
+            (Some(u), Some(i)) => self.predict_index(user, u, i),
+            _ => self.collaborative.predict(user, movie),
+        }
+    }
+
+    fn user_recs(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        let m = self.content.matrix();
+        let Some(u) = m.user(user) else {
+            return Vec::new();
+        };
+        m.recommend(u, count, |i| self.predict_index(user, u, i))
+    }
+
+    fn item_recs(&self, movie: &str, count: usize) -> Vec<(&str, f32)> {
+        let m = self.content.matrix();
+        let Some(i) = m.item(movie) else {
+            return Vec::new();
+        };
+        let mut result: Vec<_> = (0..m.items.len())
+            .filter(|&j| j != i)
+            .map(|j| (m.items[j].as_str(), self.similarity_index(i, j)))
+            .collect();
+        top(&mut result, count);
+        result
+    }
+
+    fn item_similarity(&self, a: &str, b: &str) -> Option<f32> {
+        let m = self.content.matrix();
+        Some(self.similarity_index(m.item(a)?, m.item(b)?))
+    }
+
+    /// Only matrix factorization knows about tastes of users.
+    fn similar_users(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        self.collaborative.similar_users(user, count)
+    }
+
+    fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]) {
+        self.collaborative.add_user(name, ratings);
+        self.content.add_user(name, ratings);
+    }
+
+    /// Blends the contributions from both models, the same way as the predictions.
+    fn explain(&self, user: &str, movie: &str) -> Vec<(&str, f32)> {
+        let Some(i) = self.content.matrix().item(movie) else {
+            return Vec::new();
+        };
+        let weight = self.weight(i);
+        let mut contributions = HashMap::<&str, f32>::new();
+        for (id, contribution) in self.collaborative.explain(user, movie) {
+            *contributions.entry(id).or_default() += weight * contribution;
+        }
+        for (id, contribution) in self.content.explain(user, movie) {
+            *contributions.entry(id).or_default() += (1. - weight) * contribution;
+        }
+        let mut contributions: Vec<_> = contributions.into_iter().collect();
+        contributions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        contributions
+    }
+}
@@ -0,0 +1,285 @@
+//! Neighbourhood-based collaborative filtering: a rating is predicted from the ratings
+//! of the most similar users, or from the user's own ratings of the most similar movies.
+
+use crate::recommender::{RatingMatrix, Recommender, top};
+
+/// How many of the most similar users or movies a prediction is based on.
+pub(crate) const NEIGHBOURS: usize = 20;
+
+/// Similarities computed from few common ratings are scaled down by `n / (n + SHRINKAGE)`,
+/// so two users who happened to rate a single movie the same aren't considered identical.
+const SHRINKAGE: f32 = 5.;
+
+/// Whose ratings a prediction is based on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbours {
+    /// Ratings of the movie by the users most similar to the user.
+    Users,
+    /// Ratings by the user of the movies most similar to the movie.
+    Items,
+}
+
+/// How the similarity of two users, or two movies, is computed from their ratings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Similarity {
+    /// Cosine of the angle between the rating vectors, with missing ratings as zeros.
+    Cosine,
+    /// Pearson correlation of the common ratings, which ignores how harshly each one rates.
+    Pearson,
+}
+
+impl Similarity {
+    /// Computes the similarity of two rating vectors, ordered by index.
+    fn compute(self, a: &[(usize, f32)], b: &[(usize, f32)]) -> f32 {
+        let common = common(a, b);
+        if common.is_empty() {
+            return 0.;
+        }
+        let similarity = match self {
+            Similarity::Cosine => {
+                let dot: f32 = common.iter().map(|(x, y)| x * y).sum();
+                let norm = |v: &[(usize, f32)]| v.iter().map(|(_, r)| r * r).sum::<f32>().sqrt();
+                dot / (norm(a) * norm(b)).max(f32::EPSILON)
+            }
+            Similarity::Pearson => {
+                let n = common.len() as f32;
+                let mean_x = common.iter().map(|(x, _)| x).sum::<f32>() / n;
+                let mean_y = common.iter().map(|(_, y)| y).sum::<f32>() / n;
+                let (mut covariance, mut var_x, mut var_y) = (0., 0., 0.);
+                for (x, y) in &common {
+                    covariance += (x - mean_x) * (y - mean_y);
+                    var_x += (x - mean_x).powi(2);
+                    var_y += (y - mean_y).powi(2);
+                }
+                if var_x == 0. || var_y == 0. {
+                    return 0.;
+                }
+                covariance / (var_x * var_y).sqrt()
+            }
+        };
+        let n = common.len() as f32;
+        similarity * n / (n + SHRINKAGE)
+    }
+}
+
+/// Returns the pairs of values with the same index in both vectors.
+fn common(a: &[(usize, f32)], b: &[(usize, f32)]) -> Vec<(f32, f32)> {
+    let (mut a, mut b) = (a.iter().peekable(), b.iter().peekable());
+    let mut result = Vec::new();
+    while let (Some(&&(i, x)), Some(&&(j, y))) = (a.peek(), b.peek()) {
+        match i.cmp(&j) {
+            std::cmp::Ordering::Less => _ = a.next(),
+            std::cmp::Ordering::Greater => _ = b.next(),
+            std::cmp::Ordering::Equal => {
+                result.push((x, y));
+                a.next();
+                b.next();
+            }
+        }
+    }
+    result
+}
+
+/// Values which don't depend on the movie, computed once for all recommendations of a user.
+struct Cached {
+    /// Mean rating of every user or movie, whichever the predictions are based on.
+    means: Vec<f32>,
+    /// With [user neighbours](Neighbours::Users), the similarity of the user to every other one.
+    similarities: Option<Vec<f32>>,
+}
+
+/// k-nearest neighbours collaborative filtering.
+///
+/// Similarities are computed when needed, rather than for all pairs up front. A prediction
+/// only needs the similarities to the few users who rated the movie, or to the few movies
+/// the user rated, and all pairs would take quadratic memory on larger datasets.
+#[derive(Debug, Clone)]
+pub struct Knn {
+    matrix: RatingMatrix,
+    neighbours: Neighbours,
+    similarity: Similarity,
+}
+
+impl Knn {
+    pub fn new(
+        ratings: &[(&str, &str, f32)],
+        neighbours: Neighbours,
+        similarity: Similarity,
+    ) -> Knn {
+        Knn {
+            matrix: RatingMatrix::new(ratings),
+            neighbours,
+            similarity,
+        }
+    }
+
+    fn vectors(&self, kind: Neighbours) -> &[Vec<(usize, f32)>] {
+        match kind {
+            Neighbours::Users => &self.matrix.by_user,
+            Neighbours::Items => &self.matrix.by_item,
+        }
+    }
+
+    /// Returns the similarity of two users or two movies.
+    fn pair(&self, kind: Neighbours, a: usize, b: usize) -> f32 {
+        let vectors = self.vectors(kind);
+        self.similarity.compute(&vectors[a], &vectors[b])
+    }
+
+    fn most_similar(&self, kind: Neighbours, index: usize, count: usize) -> Vec<(&str, f32)> {
+        let ids = match kind {
+            Neighbours::Users => &self.matrix.users,
+            Neighbours::Items => &self.matrix.items,
+        };
+        let mut result: Vec<_> = (0..ids.len())
+            .filter(|&other| other != index)
+            .map(|other| (ids[other].as_str(), self.pair(kind, index, other)))
+            .collect();
+        top(&mut result, count);
+        result
+    }
+
+    /// Returns the base rating a prediction starts from, and the neighbours it's adjusted by,
+    /// as `(index, similarity, deviation from their mean)`.
+    ///
+    /// For user neighbours, these are the most similar users who rated the movie, and their
+    /// ratings compared to their mean. For item neighbours, these are the most similar movies
+    /// the user rated, and the user's ratings compared to the mean of these movies.
+    ///
+    /// Similarities and means which aren't `cached` are computed.
+    fn neighbours(
+        &self,
+        u: usize,
+        i: usize,
+        cached: Option<&Cached>,
+    ) -> (f32, Vec<(usize, f32, f32)>) {
+        let m = &self.matrix;
+        let (base, candidates) = match self.neighbours {
+            Neighbours::Users => (m.mean(&m.by_user[u]), &m.by_item[i]),
+            Neighbours::Items => (m.mean(&m.by_item[i]), &m.by_user[u]),
+        };
+        let own = match self.neighbours {
+            Neighbours::Users => u,
+            Neighbours::Items => i,
+        };
+        let others = self.vectors(self.neighbours);
+        let mut neighbours: Vec<_> = candidates
+            .iter()
+            .filter(|&&(other, _)| other != own)
+            .map(|&(other, rating)| {
+                let similarity = match cached.and_then(|c| c.similarities.as_ref()) {
+                    Some(similarities) => similarities[other],
+                    None => self.pair(self.neighbours, own, other),
+                };
+                let mean = match cached {
+                    Some(cached) => cached.means[other],
+                    None => m.mean(&others[other]),
+                };
+                (other, similarity, rating - mean)
+            })
+            .filter(|&(_, similarity, _)| similarity > 0.)
+            .collect();
+        neighbours.sort_by(|a, b| b.1.total_cmp(&a.1));
+        neighbours.truncate(NEIGHBOURS);
+        (base, neighbours)
+    }
+
+    fn predict_index(&self, u: usize, i: usize, cached: Option<&Cached>) -> f32 {
+        let (base, neighbours) = self.neighbours(u, i, cached);
+        let total: f32 = neighbours
+            .iter()
+            .map(|&(_, similarity, _)| similarity)
+            .sum();
+        if total == 0. {
+            return base;
+        }
+        let adjustment: f32 = neighbours
+            .iter()
+            .map(|&(_, similarity, deviation)| similarity * deviation)
+            .sum();
+        base + adjustment / total
+    }
+}
+
+impl Recommender for Knn {
+    fn has_user(&self, user: &str) -> bool {
+        self.matrix.user(user).is_some()
+    }
+
+    fn predict(&self, user: &str, movie: &str) -> f32 {
+        let m = &self.matrix;
+        match (m.user(user), m.item(movie)) {
+            (Some(u), Some(i)) => self.predict_index(u, i, None),
+            (Some(u), None) => m.mean(&m.by_user[u]),
+            (None, Some(i)) => m.mean(&m.by_item[i]),
+            (None, None) => m.global_mean,
+        }
+    }
+
+    fn user_recs(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(u) = self.matrix.user(user) else {
+            return Vec::new();
+        };
+        // Every movie is scored, so whatever doesn't depend on the movie is computed only once.
+        let others = self.vectors(self.neighbours);
+        let cached = Cached {
+            means: others
+                .iter()
+                .map(|ratings| self.matrix.mean(ratings))
+                .collect(),
+            similarities: (self.neighbours == Neighbours::Users).then(|| {
+                (0..others.len())
+                    .map(|other| self.pair(Neighbours::Users, u, other))
+                    .collect()
+            }),
+        };
+        self.matrix
+            .recommend(u, count, |i| self.predict_index(u, i, Some(&cached)))
+    }
+
+    fn item_recs(&self, movie: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(i) = self.matrix.item(movie) else {
+            return Vec::new();
+        };
+        self.most_similar(Neighbours::Items, i, count)
+    }
+
+    fn item_similarity(&self, a: &str, b: &str) -> Option<f32> {
+        let (a, b) = (self.matrix.item(a)?, self.matrix.item(b)?);
+        Some(self.pair(Neighbours::Items, a, b))
+    }
+
+    fn similar_users(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(u) = self.matrix.user(user) else {
+            return Vec::new();
+        };
+        self.most_similar(Neighbours::Users, u, count)
+    }
+
+    /// Similarities are computed from the current ratings, so they don't need updating.
+    fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]) {
+        self.matrix.add_user(name, ratings);
+    }
+
+    /// Only predictions based on similar movies can be explained by the user's own ratings.
+    fn explain(&self, user: &str, movie: &str) -> Vec<(&str, f32)> {
+        let m = &self.matrix;
+        let (Neighbours::Items, Some(u), Some(i)) = (self.neighbours, m.user(user), m.item(movie))
+        else {
+            return Vec::new();
+        };
+        let (_, neighbours) = self.neighbours(u, i, None);
+        let total: f32 = neighbours
+            .iter()
+            .map(|&(_, similarity, _)| similarity)
+            .sum();
+        let mut contributions: Vec<_> = neighbours
+            .iter()
+            .map(|&(j, similarity, deviation)| {
+                (m.items[j].as_str(), similarity * deviation / total)
+            })
+            .collect();
+        contributions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        contributions
+    }
+}
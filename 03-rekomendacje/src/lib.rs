@@ -4,6 +4,29 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
+pub mod content;
+pub mod data;
+pub mod diversity;
+pub mod evaluation;
+pub mod feedback;
+pub mod filter;
+pub mod group;
+pub mod hybrid;
+pub mod knn;
+pub mod model;
+pub mod neighbours;
+pub mod onboarding;
+pub mod popularity;
+pub mod preprocessing;
+pub mod recommender;
+pub mod server;
+
+/// Message catalogs of the user interface, to pass to [`i18n::init`].
+pub const CATALOGS: i18n::Catalogs = i18n::Catalogs {
+    pl: include_str!("../locales/pl.txt"),
+    en: include_str!("../locales/en.txt"),
+};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Rating {
     pub movie: String,
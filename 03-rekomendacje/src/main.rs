@@ -5,57 +5,202 @@ use std::{
     env::var,
     path::{Path, PathBuf},
     sync::mpsc,
+    time::Duration,
 };
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, eyre};
 use crossterm::event::{self, Event, KeyCode, KeyEvent};
 use directories::ProjectDirs;
-use discorec::{Dataset, Recommender, RecommenderBuilder};
-use image::ImageReader;
+use i18n::{Locale, t, tr};
 use ratatui::{
     DefaultTerminal, Frame, TerminalOptions, Viewport,
     layout::{Constraint, Layout, Rect},
-    style::{Color, Style},
-    text::Text,
+    style::{Color, Modifier, Style},
+    text::{Line, Text},
     widgets::{Gauge, List, ListItem, ListState, Paragraph, Wrap},
 };
-use ratatui_image::{Resize, StatefulImage, picker::Picker};
-use rekomendacje::{ImdbTitle, User};
-use reqwest::Url;
+use ratatui_image::{Resize, StatefulImage};
+use rekomendacje::{
+    ImdbTitle, Rating, User,
+    content::Content,
+    data::Data,
+    diversity::{DEFAULT_DIVERSITY, Diversity},
+    feedback::Feedback,
+    filter::Filter,
+    group::{Aggregation, GroupRec, group_recs},
+    hybrid::Hybrid,
+    model::{Hyperparams, Model, fingerprint},
+    neighbours::{Neighbour, neighbours},
+    onboarding::movies_to_rate,
+    recommender::{Algorithm, Recommender},
+    server::Server,
+};
+
+use crate::thumbnails::{Poster, Thumbnails};
 
-const MOVIES_STR: &str = include_str!(concat!(env!("REKOMENDACJE_DATA_DIR"), "/movies.json"));
-const USERS_STR: &str = include_str!(concat!(env!("REKOMENDACJE_DATA_DIR"), "/users.json"));
-const EXTRA_USERS_STR: &str =
-    include_str!(concat!(env!("REKOMENDACJE_DATA_DIR"), "/extra_users.json"));
+mod export;
+mod thumbnails;
 
-const ITERATIONS: u32 = 1024;
-const FACTORS: u32 = 128;
+const MODEL_FILE: &str = "model.json";
+/// Feedback on the recommendations, kept next to the saved users.
+const FEEDBACK_FILE: &str = "feedback.json";
+/// Folder in the cache where posters are saved.
+const THUMBNAILS_DIR: &str = "thumbnails";
+/// How often the interface checks for loaded posters while waiting for keys.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Address the server listens on, unless another one is given with `--addr`.
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+/// How many movies a new user is asked to rate.
+const ONBOARDING_MOVIES: usize = 20;
+/// How many movies are in each list of recommendations, until changed with `+` and `-`.
+const RECS: usize = 5;
+/// The longest the lists of recommendations can get.
+const MAX_RECS: usize = 20;
+/// How much the diversity of the lists changes with each key press.
+const DIVERSITY_STEP: f32 = 0.1;
+/// How many of the movies a user rated are shown as the reason for a recommendation.
+const REASONS: usize = 2;
+/// How many movies are listed as similar to the picked one.
+const SIMILAR_MOVIES: usize = 10;
+/// How many movies are recommended to a group.
+const GROUP_RECS: usize = 10;
+const NEIGHBOURS: usize = 10;
 
 struct App<'a> {
+    data: &'a Data,
     movies: &'a [ImdbTitle],
-    users: &'a [User],
-    engine: Recommender<&'a str, &'a str>,
-    thumb_cache: &'a Path,
+    /// The same movies by id, so large datasets don't have to be searched one by one.
+    movie_index: HashMap<&'a str, &'a ImdbTitle>,
+    users: Vec<User>,
+    engine: Box<dyn Recommender>,
+    thumbnails: Thumbnails,
+    /// Where the ratings of new users are saved.
+    save_dir: &'a Path,
+    feedback: Feedback,
+    feedback_path: PathBuf,
+    /// Movies a new user is asked to rate, in order.
+    onboarding: Vec<&'a str>,
     state: AppState<'a>,
 }
 
+/// How the lists of recommendations are made.
+#[derive(Clone)]
+struct ListOptions {
+    filter: Filter,
+    /// How many movies are in each list.
+    count: usize,
+    /// From 0 to 1, see [`Diversity::rerank`].
+    diversity: f32,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            filter: Filter::default(),
+            count: RECS,
+            diversity: DEFAULT_DIVERSITY,
+        }
+    }
+}
+
+impl ListOptions {
+    /// Changes the diversity by `steps` of [`DIVERSITY_STEP`], keeping it between 0 and 1.
+    fn change_diversity(&mut self, steps: f32) {
+        let diversity = self.diversity + steps * DIVERSITY_STEP;
+        // Rounded, so repeated steps don't drift away from the round values.
+        self.diversity = ((diversity / DIVERSITY_STEP).round() * DIVERSITY_STEP).clamp(0., 1.);
+    }
+}
+
+/// What a user can tell about a recommended movie.
+#[derive(Clone, Copy)]
+enum FeedbackAction {
+    Rate(u32),
+    NotInterested,
+    Watchlist,
+}
+
 enum AppState<'a> {
     SelectingUser {
         hovered: usize,
+        /// Users picked for a group recommendation.
+        selected: Vec<usize>,
+    },
+    EnteringName {
+        name: String,
+        taken: bool,
+    },
+    RatingMovies {
+        name: String,
+        position: usize,
+        /// Rating of each of the onboarding movies, `None` if skipped.
+        ratings: Vec<Option<u32>>,
+        need_rating: bool,
     },
     CheckingRecs {
+        user: usize,
         top_recs: Vec<&'a str>,
         anti_recs: Vec<&'a str>,
         anti_recs_hovered: bool,
         hovered: usize,
+        /// Movies the user rated which contributed the most to each recommendation.
+        reasons: HashMap<&'a str, Vec<&'a str>>,
+        /// Whether the user was just added, and their ratings can be saved.
+        unsaved: bool,
+        message: Option<String>,
+        options: Box<ListOptions>,
+        /// Text of the filter being typed, while the filter bar is open.
+        editing: Option<String>,
+        /// Movie last marked as not interesting, which can be brought back with [u].
+        dismissed: Option<&'a str>,
+    },
+    SimilarMovies {
+        movie: &'a str,
+        similar: Vec<&'a str>,
+        hovered: usize,
+        /// Screen to go back to.
+        back: Box<AppState<'a>>,
+    },
+    Neighbours {
+        user: usize,
+        neighbours: Vec<Neighbour>,
+        hovered: usize,
+        /// Screen to go back to.
+        back: Box<AppState<'a>>,
+    },
+    /// Ratings of `user`, next to the ratings of `viewer` who opened them.
+    UserRatings {
+        user: usize,
+        viewer: usize,
+        /// Rated movies, from the highest rating.
+        ratings: Vec<(&'a str, u32)>,
+        hovered: usize,
+        /// Screen to go back to.
+        back: Box<AppState<'a>>,
+    },
+    GroupRecs {
+        members: Vec<usize>,
+        aggregation: Aggregation,
+        recs: Vec<GroupRec<'a>>,
+        hovered: usize,
     },
 }
 
 impl<'a> App<'a> {
     pub fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        let mut redraw = true;
         loop {
-            terminal.draw(|frame| self.render(frame))?;
+            // Posters are loaded in the background, and shown as soon as they're ready.
+            redraw |= self.thumbnails.update();
+            if redraw {
+                terminal.draw(|frame| self.render(frame))?;
+                redraw = false;
+            }
 
+            if !event::poll(POLL_INTERVAL)? {
+                continue;
+            }
+            redraw = true;
             match event::read()? {
                 Event::Key(e) if e.is_press() => {
                     if !self.handle_key(e) {
@@ -69,168 +214,870 @@ impl<'a> App<'a> {
     }
 
     fn handle_key(&mut self, event: KeyEvent) -> bool {
+        match self.state {
+            AppState::EnteringName { .. } => {
+                self.handle_name_key(event);
+                return true;
+            }
+            AppState::RatingMovies { .. } => {
+                self.handle_rating_key(event);
+                return true;
+            }
+            AppState::CheckingRecs {
+                editing: Some(_), ..
+            } => {
+                self.handle_filter_key(event);
+                return true;
+            }
+            AppState::SimilarMovies { .. }
+            | AppState::Neighbours { .. }
+            | AppState::UserRatings { .. }
+                if event.code == KeyCode::Esc =>
+            {
+                match self.take_state() {
+                    AppState::SimilarMovies { back, .. }
+                    | AppState::Neighbours { back, .. }
+                    | AppState::UserRatings { back, .. } => self.state = *back,
+                    _ => unreachable!(),
+                }
+                return true;
+            }
+            AppState::GroupRecs { ref members, .. } if event.code == KeyCode::Esc => {
+                self.state = AppState::SelectingUser {
+                    hovered: 0,
+                    selected: members.clone(),
+                };
+                return true;
+            }
+            _ => {}
+        }
+
         match event.code {
             KeyCode::Char('q') | KeyCode::Esc => return false,
             KeyCode::Char('j') | KeyCode::Down => self.hovered_diff(1),
             KeyCode::Char('k') | KeyCode::Up => self.hovered_diff(-1),
-            KeyCode::Enter | KeyCode::Char(' ') => {
-                if let AppState::SelectingUser { hovered } = self.state {
-                    let recs = self
-                        .engine
-                        .user_recs(&self.users[hovered].name.as_str(), self.movies.len());
-                    let rec_count = (recs.len() / 2).min(5);
-                    let top_recs = recs.iter().map(|&(&id, _)| id).take(rec_count).collect();
-                    let anti_recs = recs
-                        .iter()
-                        .map(|&(&id, _)| id)
-                        .rev()
-                        .take(rec_count)
-                        .collect();
-                    self.state = AppState::CheckingRecs {
-                        top_recs,
-                        anti_recs,
-                        anti_recs_hovered: false,
-                        hovered: 0,
+            KeyCode::Char(' ') if matches!(self.state, AppState::SelectingUser { .. }) => {
+                if let AppState::SelectingUser {
+                    hovered,
+                    ref mut selected,
+                } = self.state
+                    && hovered < self.users.len()
+                {
+                    match selected.iter().position(|&user| user == hovered) {
+                        Some(index) => _ = selected.remove(index),
+                        None => selected.push(hovered),
+                    }
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => match self.state {
+                AppState::SelectingUser {
+                    hovered,
+                    ref selected,
+                } => {
+                    if selected.len() >= 2 {
+                        self.show_group(selected.clone(), Aggregation::default());
+                    } else if hovered < self.users.len() {
+                        self.show_recs(hovered, false, ListOptions::default());
+                    } else if !self.onboarding.is_empty() {
+                        self.state = AppState::EnteringName {
+                            name: String::new(),
+                            taken: false,
+                        };
+                    }
+                }
+                AppState::CheckingRecs {
+                    ref top_recs,
+                    ref anti_recs,
+                    anti_recs_hovered,
+                    hovered,
+                    ..
+                } => {
+                    let set = if anti_recs_hovered {
+                        anti_recs
+                    } else {
+                        top_recs
                     };
+                    if let Some(&movie) = set.get(hovered) {
+                        self.show_similar(movie);
+                    }
+                }
+                AppState::SimilarMovies {
+                    ref similar,
+                    hovered,
+                    ..
+                } => {
+                    if let Some(&movie) = similar.get(hovered) {
+                        self.show_similar(movie);
+                    }
                 }
+                AppState::GroupRecs {
+                    ref recs, hovered, ..
+                } => {
+                    if let Some(rec) = recs.get(hovered) {
+                        self.show_similar(rec.movie);
+                    }
+                }
+                AppState::Neighbours {
+                    user,
+                    ref neighbours,
+                    hovered,
+                    ..
+                } => {
+                    if let Some(neighbour) = neighbours.get(hovered) {
+                        self.show_ratings(neighbour.user, user);
+                    }
+                }
+                AppState::UserRatings {
+                    ref ratings,
+                    hovered,
+                    ..
+                } => {
+                    if let Some(&(movie, _)) = ratings.get(hovered) {
+                        self.show_similar(movie);
+                    }
+                }
+                _ => {}
+            },
+            // 1-9, and 0 for 10, like when rating the first movies.
+            KeyCode::Char(c @ '0'..='9') => {
+                let rating = c.to_digit(10).unwrap();
+                self.give_feedback(FeedbackAction::Rate(if rating == 0 { 10 } else { rating }));
             }
-            KeyCode::Tab => {
+            KeyCode::Char('x') => self.give_feedback(FeedbackAction::NotInterested),
+            KeyCode::Char('u') => self.undo_not_interested(),
+            KeyCode::Char('w') => self.give_feedback(FeedbackAction::Watchlist),
+            KeyCode::Char('n') => {
+                if let AppState::CheckingRecs { user, .. } = self.state {
+                    self.show_neighbours(user);
+                }
+            }
+            KeyCode::Char('s') => {
                 if let AppState::CheckingRecs {
-                    ref mut anti_recs_hovered,
-                    ref mut hovered,
+                    user,
+                    ref mut unsaved,
+                    ref mut message,
+                    ..
+                } = self.state
+                    && *unsaved
+                {
+                    match self.data.save_user(self.save_dir, &self.users[user]) {
+                        Ok(()) => {
+                            *unsaved = false;
+                            *message = Some(tr!("recs.saved", path = self.save_dir.display()));
+                        }
+                        Err(e) => *message = Some(tr!("recs.save_failed", error = e)),
+                    }
+                }
+            }
+            KeyCode::Char('/') => {
+                if let AppState::CheckingRecs {
+                    ref options,
+                    ref mut editing,
                     ..
                 } = self.state
                 {
+                    *editing = Some(options.filter.query.clone());
+                }
+            }
+            KeyCode::Char('d') => self.refilter(|options| options.filter.new_directors ^= true),
+            KeyCode::Char('+' | '=') => {
+                self.refilter(|options| options.count = (options.count + 1).min(MAX_RECS));
+            }
+            KeyCode::Char('-') => {
+                self.refilter(|options| options.count = options.count.saturating_sub(1).max(1));
+            }
+            KeyCode::Char('[') => self.refilter(|options| options.change_diversity(-1.)),
+            KeyCode::Char(']') => self.refilter(|options| options.change_diversity(1.)),
+            KeyCode::Tab => match self.state {
+                AppState::CheckingRecs {
+                    ref mut anti_recs_hovered,
+                    ref mut hovered,
+                    ..
+                } => {
                     *anti_recs_hovered = !*anti_recs_hovered;
                     *hovered = 0;
                 }
-            }
+                AppState::GroupRecs {
+                    ref members,
+                    aggregation,
+                    ..
+                } => self.show_group(members.clone(), aggregation.next()),
+                _ => {}
+            },
             _ => {}
         }
         true
     }
 
+    fn handle_name_key(&mut self, event: KeyEvent) {
+        let AppState::EnteringName { name, taken } = &mut self.state else {
+            return;
+        };
+        match event.code {
+            KeyCode::Esc => {
+                self.state = AppState::SelectingUser {
+                    hovered: 0,
+                    selected: Vec::new(),
+                }
+            }
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c) => name.push(c),
+            KeyCode::Enter => {
+                let name = name.trim();
+                if name.is_empty() {
+                    return;
+                }
+                if self.users.iter().any(|user| user.name == name) || self.engine.has_user(name) {
+                    *taken = true;
+                    return;
+                }
+                self.state = AppState::RatingMovies {
+                    name: name.to_string(),
+                    position: 0,
+                    ratings: vec![None; self.onboarding.len()],
+                    need_rating: false,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_filter_key(&mut self, event: KeyEvent) {
+        let AppState::CheckingRecs { editing, .. } = &mut self.state else {
+            return;
+        };
+        let Some(text) = editing else {
+            return;
+        };
+        match event.code {
+            KeyCode::Esc => *editing = None,
+            KeyCode::Backspace => _ = text.pop(),
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Enter => {
+                let query = std::mem::take(text);
+                self.refilter(|options| {
+                    options.filter = Filter {
+                        new_directors: options.filter.new_directors,
+                        ..Filter::parse(&query)
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_rating_key(&mut self, event: KeyEvent) {
+        let AppState::RatingMovies {
+            position,
+            ratings,
+            need_rating,
+            ..
+        } = &mut self.state
+        else {
+            return;
+        };
+        match event.code {
+            KeyCode::Esc => {
+                self.state = AppState::SelectingUser {
+                    hovered: 0,
+                    selected: Vec::new(),
+                };
+                return;
+            }
+            // 1-9, and 0 for 10, like on the keyboard.
+            KeyCode::Char(c @ '0'..='9') => {
+                let rating = c.to_digit(10).unwrap();
+                ratings[*position] = Some(if rating == 0 { 10 } else { rating });
+                *position += 1;
+            }
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Char(' ') => *position += 1,
+            KeyCode::Char('h') | KeyCode::Left => *position = position.saturating_sub(1),
+            KeyCode::Enter => {
+                if ratings.iter().all(Option::is_none) {
+                    *need_rating = true;
+                } else {
+                    self.finish_rating();
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        // Once every movie was shown, the recommendations come up on their own.
+        if *position >= ratings.len() {
+            if ratings.iter().all(Option::is_none) {
+                *position = ratings.len().saturating_sub(1);
+                *need_rating = true;
+            } else {
+                self.finish_rating();
+            }
+        }
+    }
+
+    /// Adds the new user with their ratings to the engine, and shows their recommendations.
+    fn finish_rating(&mut self) {
+        let AppState::RatingMovies { name, ratings, .. } = &self.state else {
+            return;
+        };
+        let ratings: Vec<Rating> = self
+            .onboarding
+            .iter()
+            .zip(ratings)
+            .filter_map(|(movie, rating)| Some(Rating::new(movie.to_string(), (*rating)?)))
+            .collect();
+        let engine_ratings: Vec<_> = ratings
+            .iter()
+            .map(|rating| (rating.movie.as_str(), rating.rating as f32))
+            .collect();
+        self.engine.add_user(name, &engine_ratings);
+
+        self.users.push(User {
+            name: name.clone(),
+            ratings,
+        });
+        self.show_recs(self.users.len() - 1, true, ListOptions::default());
+    }
+
+    /// Returns the id of the movie from the movie list.
+    ///
+    /// Ids returned by the engine only live as long as the engine,
+    /// so they're swapped for the same ids from the movie list, which live longer.
+    fn movie_id(&self, id: &str) -> Option<&'a str> {
+        self.movie_index.get(id).map(|movie| movie.id.as_str())
+    }
+
+    /// Records feedback on the hovered recommendation, saves it, and updates the lists.
+    fn give_feedback(&mut self, action: FeedbackAction) {
+        let AppState::CheckingRecs {
+            user,
+            ref top_recs,
+            ref anti_recs,
+            anti_recs_hovered,
+            hovered,
+            mut dismissed,
+            ..
+        } = self.state
+        else {
+            return;
+        };
+        let set = if anti_recs_hovered {
+            anti_recs
+        } else {
+            top_recs
+        };
+        let Some(&movie) = set.get(hovered) else {
+            return;
+        };
+        let movie_title = title(self.movie_index[movie]);
+        let name = self.users[user].name.clone();
+        let feedback = self.feedback.user_mut(&name);
+        let message = match action {
+            FeedbackAction::Rate(rating) => {
+                feedback.rate(movie, rating);
+                // The engine learns the rating right away, the model is trained on it next time.
+                let ratings = &mut self.users[user].ratings;
+                match ratings.iter_mut().find(|r| r.movie == movie) {
+                    Some(existing) => existing.rating = rating,
+                    None => ratings.push(Rating::new(movie.to_string(), rating)),
+                }
+                let engine_ratings: Vec<_> = ratings
+                    .iter()
+                    .map(|rating| (rating.movie.as_str(), rating.rating as f32))
+                    .collect();
+                self.engine.add_user(&name, &engine_ratings);
+                tr!("feedback.rated", movie = movie_title, rating = rating)
+            }
+            FeedbackAction::NotInterested => {
+                // Movies marked before aren't on the lists, so the hovered one can only be marked now.
+                feedback.toggle_not_interested(movie);
+                dismissed = Some(movie);
+                tr!("feedback.not_interested", movie = movie_title)
+            }
+            FeedbackAction::Watchlist => {
+                if feedback.toggle_watchlist(movie) {
+                    tr!("feedback.watchlist_added", movie = movie_title)
+                } else {
+                    tr!("feedback.watchlist_removed", movie = movie_title)
+                }
+            }
+        };
+        self.save_feedback(message, anti_recs_hovered, hovered, dismissed);
+    }
+
+    /// Brings back the movie last marked as not interesting, on the lists and in the saved feedback.
+    fn undo_not_interested(&mut self) {
+        let AppState::CheckingRecs {
+            user,
+            anti_recs_hovered,
+            hovered,
+            dismissed: Some(movie),
+            ..
+        } = self.state
+        else {
+            return;
+        };
+        let name = self.users[user].name.clone();
+        self.feedback.user_mut(&name).toggle_not_interested(movie);
+        let message = tr!(
+            "feedback.interested",
+            movie = title(self.movie_index[movie])
+        );
+        self.save_feedback(message, anti_recs_hovered, hovered, None);
+    }
+
+    /// Saves the feedback, shows `message` (or the saving error),
+    /// and makes the lists again, staying at the same place.
+    fn save_feedback(
+        &mut self,
+        message: String,
+        anti_recs_hovered: bool,
+        hovered: usize,
+        dismissed: Option<&'a str>,
+    ) {
+        let message = match self.feedback.save(&self.feedback_path) {
+            Ok(()) => message,
+            Err(e) => tr!("feedback.save_failed", error = e),
+        };
+
+        self.refilter(|_| {});
+        if let AppState::CheckingRecs {
+            top_recs,
+            anti_recs,
+            anti_recs_hovered: new_anti_recs_hovered,
+            hovered: new_hovered,
+            message: new_message,
+            dismissed: new_dismissed,
+            ..
+        } = &mut self.state
+        {
+            let set = if anti_recs_hovered {
+                anti_recs
+            } else {
+                top_recs
+            };
+            *new_anti_recs_hovered = anti_recs_hovered;
+            *new_hovered = hovered.min(set.len().saturating_sub(1));
+            *new_message = Some(message);
+            *new_dismissed = dismissed;
+        }
+    }
+
+    /// Changes how the lists are made, and shows the recommendations again.
+    fn refilter(&mut self, change: impl FnOnce(&mut ListOptions)) {
+        if let AppState::CheckingRecs {
+            user,
+            unsaved,
+            ref options,
+            ..
+        } = self.state
+        {
+            let mut options = ListOptions::clone(options);
+            change(&mut options);
+            self.show_recs(user, unsaved, options);
+        }
+    }
+
+    fn show_recs(&mut self, user: usize, unsaved: bool, options: ListOptions) {
+        let name = &self.users[user].name;
+        let movies = self.movies;
+        let matches = options.filter.matcher(&self.users[user], movies);
+        let not_interested = &self.feedback.user(name).not_interested;
+        let recs: Vec<(&'a str, f32)> = self
+            .engine
+            .user_recs(name, movies.len())
+            .iter()
+            .filter_map(|&(id, score)| {
+                let movie = *self.movie_index.get(id)?;
+                let shown = matches(movie) && !not_interested.contains(&movie.id);
+                shown.then_some((movie.id.as_str(), score))
+            })
+            .collect();
+        drop(matches);
+        // With few movies left after filtering, both lists get half of them, so they don't overlap.
+        let (best, worst) = recs.split_at(recs.len() / 2);
+        let worst: Vec<_> = worst
+            .iter()
+            .rev()
+            .map(|&(id, score)| (id, -score))
+            .collect();
+        let rec_count = best.len().min(options.count);
+        let diversity = Diversity::new(self.engine.as_ref(), movies);
+        let rerank = |ranked: &[(&'a str, f32)]| -> Vec<&'a str> {
+            let reranked = diversity.rerank(ranked, options.diversity, rec_count);
+            reranked.into_iter().map(|(id, _)| id).collect()
+        };
+        let top_recs = rerank(best);
+        let anti_recs = rerank(&worst);
+
+        let mut reasons = HashMap::new();
+        for &movie in &top_recs {
+            let explanation = self.engine.explain(name, movie);
+            let liked = explanation
+                .iter()
+                .take_while(|(_, contribution)| *contribution > 0.)
+                .take(REASONS)
+                .filter_map(|&(id, _)| self.movie_id(id));
+            reasons.insert(movie, liked.collect());
+        }
+        for &movie in &anti_recs {
+            let explanation = self.engine.explain(name, movie);
+            let disliked = explanation
+                .iter()
+                .rev()
+                .take_while(|(_, contribution)| *contribution < 0.)
+                .take(REASONS)
+                .filter_map(|&(id, _)| self.movie_id(id));
+            reasons.insert(movie, disliked.collect());
+        }
+
+        self.state = AppState::CheckingRecs {
+            user,
+            top_recs,
+            anti_recs,
+            anti_recs_hovered: false,
+            hovered: 0,
+            reasons,
+            unsaved,
+            message: None,
+            options: Box::new(options),
+            editing: None,
+            dismissed: None,
+        };
+    }
+
+    /// Takes the current state out, leaving the list of users in its place.
+    fn take_state(&mut self) -> AppState<'a> {
+        std::mem::replace(
+            &mut self.state,
+            AppState::SelectingUser {
+                hovered: 0,
+                selected: Vec::new(),
+            },
+        )
+    }
+
+    fn show_similar(&mut self, movie: &'a str) {
+        let similar = self
+            .engine
+            .item_recs(movie, SIMILAR_MOVIES)
+            .iter()
+            .filter_map(|&(id, _)| self.movie_id(id))
+            .collect();
+        let back = self.take_state();
+        self.state = AppState::SimilarMovies {
+            movie,
+            similar,
+            hovered: 0,
+            back: Box::new(back),
+        };
+    }
+
+    fn show_neighbours(&mut self, user: usize) {
+        let neighbours = neighbours(self.engine.as_ref(), &self.users, user, NEIGHBOURS);
+        let back = self.take_state();
+        self.state = AppState::Neighbours {
+            user,
+            neighbours,
+            hovered: 0,
+            back: Box::new(back),
+        };
+    }
+
+    fn show_ratings(&mut self, user: usize, viewer: usize) {
+        let mut ratings: Vec<_> = self.users[user]
+            .ratings
+            .iter()
+            .filter_map(|rating| Some((self.movie_id(&rating.movie)?, rating.rating)))
+            .collect();
+        ratings.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let back = self.take_state();
+        self.state = AppState::UserRatings {
+            user,
+            viewer,
+            ratings,
+            hovered: 0,
+            back: Box::new(back),
+        };
+    }
+
+    fn show_group(&mut self, members: Vec<usize>, aggregation: Aggregation) {
+        let names: Vec<_> = members
+            .iter()
+            .map(|&user| self.users[user].name.as_str())
+            .collect();
+        let recs = group_recs(self.engine.as_ref(), &names, aggregation, GROUP_RECS)
+            .into_iter()
+            .filter_map(|rec| {
+                Some(GroupRec {
+                    movie: self.movie_id(rec.movie)?,
+                    ..rec
+                })
+            })
+            .collect();
+        self.state = AppState::GroupRecs {
+            members,
+            aggregation,
+            recs,
+            hovered: 0,
+        };
+    }
+
     fn hovered_diff(&mut self, diff: isize) {
         match &mut self.state {
-            AppState::SelectingUser { hovered } => {
-                *hovered = hovered.saturating_add_signed(diff).min(self.users.len());
+            AppState::SelectingUser { hovered, .. } => {
+                // The last entry, after all users, is for new users,
+                // unless there are no movies for them to rate.
+                let last = self
+                    .users
+                    .len()
+                    .saturating_sub(usize::from(self.onboarding.is_empty()));
+                *hovered = hovered.saturating_add_signed(diff).min(last);
             }
+            AppState::EnteringName { .. } | AppState::RatingMovies { .. } => {}
             AppState::CheckingRecs {
                 hovered,
                 top_recs,
                 anti_recs,
                 anti_recs_hovered,
+                ..
             } => {
                 let set = if *anti_recs_hovered {
                     anti_recs
                 } else {
                     top_recs
                 };
-                *hovered = hovered.saturating_add_signed(diff).min(set.len() - 1);
+                *hovered = hovered
+                    .saturating_add_signed(diff)
+                    .min(set.len().saturating_sub(1));
+            }
+            AppState::SimilarMovies {
+                hovered, similar, ..
+            } => {
+                *hovered = hovered
+                    .saturating_add_signed(diff)
+                    .min(similar.len().saturating_sub(1));
+            }
+            AppState::GroupRecs { hovered, recs, .. } => {
+                *hovered = hovered
+                    .saturating_add_signed(diff)
+                    .min(recs.len().saturating_sub(1));
+            }
+            AppState::Neighbours {
+                hovered,
+                neighbours,
+                ..
+            } => {
+                *hovered = hovered
+                    .saturating_add_signed(diff)
+                    .min(neighbours.len().saturating_sub(1));
+            }
+            AppState::UserRatings {
+                hovered, ratings, ..
+            } => {
+                *hovered = hovered
+                    .saturating_add_signed(diff)
+                    .min(ratings.len().saturating_sub(1));
             }
         }
     }
 
     fn render(&mut self, frame: &mut Frame) {
         match &self.state {
-            AppState::SelectingUser { hovered } => {
-                App::render_selecting_user(frame, *hovered, self.users)
+            AppState::SelectingUser { hovered, selected } => {
+                let new_user = !self.onboarding.is_empty();
+                App::render_selecting_user(frame, *hovered, selected, &self.users, new_user)
             }
-            AppState::CheckingRecs {
+            AppState::EnteringName { name, taken } => {
+                App::render_entering_name(frame, name, *taken)
+            }
+            AppState::RatingMovies {
+                position,
+                ratings,
+                need_rating,
+                ..
+            } => {
+                let (onboarding, movies) = (&self.onboarding, &self.movie_index);
+                let find = |i: usize| movies.get(onboarding.get(i)?).copied();
+                self.thumbnails.prefetch(find, *position);
+                App::render_rating(
+                    frame,
+                    &mut self.thumbnails,
+                    find(*position).unwrap(),
+                    *position,
+                    ratings,
+                    *need_rating,
+                )
+            }
+            AppState::CheckingRecs { .. } => self.render_recs(frame),
+            AppState::SimilarMovies { .. } => self.render_similar(frame),
+            AppState::GroupRecs { .. } => self.render_group(frame),
+            AppState::Neighbours {
+                user,
+                neighbours,
                 hovered,
-                top_recs,
-                anti_recs,
-                anti_recs_hovered,
-            } => App::render_recs(
-                frame,
-                self.thumb_cache,
-                self.movies,
-                top_recs,
-                anti_recs,
-                *anti_recs_hovered,
-                *hovered,
-            ),
+                ..
+            } => App::render_neighbours(frame, &self.users, *user, neighbours, *hovered),
+            AppState::UserRatings { .. } => self.render_ratings(frame),
         }
     }
 
-    fn render_selecting_user(frame: &mut Frame, hovered: usize, users: &'a [User]) {
-        let v = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
-        let [label, rest] = v.areas(frame.area());
+    fn render_selecting_user(
+        frame: &mut Frame,
+        hovered: usize,
+        selected: &[usize],
+        users: &[User],
+        new_user: bool,
+    ) {
+        let v = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ]);
+        let [label, rest, help] = v.areas(frame.area());
 
-        let text = Text::raw("Kim jesteś").centered();
+        let text = Text::raw(t("users.title")).centered();
 
         let item_style = Style::new();
+        let new_user_style = Style::new().add_modifier(Modifier::ITALIC);
         let items = users
             .iter()
-            .map(|user| ListItem::new(user.name.as_str()).style(item_style));
+            .enumerate()
+            .map(|(i, user)| {
+                let mark = if selected.contains(&i) { "[x]" } else { "[ ]" };
+                ListItem::new(format!("{mark} {}", user.name)).style(item_style)
+            })
+            .chain(new_user.then(|| ListItem::new(t("users.new")).style(new_user_style)));
         let list =
             List::new(items).highlight_style(Style::new().bg(Color::LightBlue).fg(Color::Black));
         let mut state = ListState::default().with_selected(Some(hovered));
 
         frame.render_widget(text, label);
         frame.render_stateful_widget(list, rest, &mut state);
+        frame.render_widget(Text::raw(t("users.help")), help);
     }
 
-    fn thumb_path(movie: &ImdbTitle, cache: &Path) -> Option<PathBuf> {
-        std::fs::create_dir_all(cache).unwrap();
-        let url = movie.primary_image.as_ref()?.url.as_str();
-        let url = Url::parse(url).unwrap();
-        let (_, ext) = url.path().rsplit_once('.').unwrap();
-        let mut p = cache.join(&movie.id);
-        p.set_extension(ext);
-        if p.is_file() {
-            return Some(p);
-        }
-        let img = reqwest::blocking::get(url)
-            .unwrap()
-            .error_for_status()
-            .unwrap()
-            .bytes()
-            .unwrap();
-        std::fs::write(&p, img).unwrap();
-        Some(p)
+    fn render_entering_name(frame: &mut Frame, name: &str, taken: bool) {
+        let text = Text::from(vec![
+            Line::from(t("onboarding.name")),
+            Line::from(format!("> {name}_")),
+            Line::from(if taken {
+                t("onboarding.name_taken")
+            } else {
+                ""
+            })
+            .style(Style::new().fg(Color::Red)),
+        ]);
+        frame.render_widget(text, frame.area());
     }
 
-    fn render_recs(
+    fn render_rating(
         frame: &mut Frame,
-        cache: &Path,
-        movies: &[ImdbTitle],
-        top_recs: &[&str],
-        anti_recs: &[&str],
-        anti_recs_hovered: bool,
-        hovered: usize,
+        thumbnails: &mut Thumbnails,
+        movie: &ImdbTitle,
+        position: usize,
+        ratings: &[Option<u32>],
+        need_rating: bool,
     ) {
-        let v = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]);
+        let v = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ]);
+        let [progress, movie_area, rating, help] = v.areas(frame.area());
+
+        let rated = ratings.iter().flatten().count();
+        let progress_text = tr!(
+            "onboarding.progress",
+            position = position + 1,
+            total = ratings.len(),
+            rated = rated
+        );
+        let rating_line = if need_rating {
+            Line::from(t("onboarding.need_rating")).style(Style::new().fg(Color::Red))
+        } else {
+            match ratings[position] {
+                Some(rating) => Line::from(tr!("onboarding.your_rating", rating = rating)),
+                None => Line::from(t("onboarding.not_rated")),
+            }
+        };
+
+        frame.render_widget(Text::raw(progress_text).centered(), progress);
+        App::render_movie(frame, thumbnails, movie, movie_area);
+        frame.render_widget(rating_line.centered(), rating);
+        frame.render_widget(Text::raw(t("onboarding.help")), help);
+    }
+
+    fn render_recs(&mut self, frame: &mut Frame) {
+        let AppState::CheckingRecs {
+            user,
+            top_recs,
+            anti_recs,
+            anti_recs_hovered,
+            hovered,
+            reasons,
+            unsaved,
+            message,
+            options,
+            editing,
+            ..
+        } = &self.state
+        else {
+            return;
+        };
+        let (anti_recs_hovered, hovered) = (*anti_recs_hovered, *hovered);
+        let movies = &self.movie_index;
+        let watchlist = &self.feedback.user(&self.users[*user].name).watchlist;
+
+        let v = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ]);
         let h = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]);
-        let [area, help] = v.areas(frame.area());
+        let [area, filter_area, help] = v.areas(frame.area());
         let [left, right] = h.areas(area);
 
-        let v = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
-        let [title_area, mut right] = v.areas(right);
-
         let split = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]);
         let [top_area, anti_area] = split.areas(left);
 
         let highlight_style = Style::new().bg(Color::LightBlue).fg(Color::Black);
 
-        let list_from_ids = |ids: &[&str]| {
+        let reason_style = Style::new().add_modifier(Modifier::DIM);
+
+        let list_from_ids = |ids: &[&str], reason_key: &'static str| {
             List::new(ids.iter().map(|id| {
-                let movie = movies.iter().find(|m| m.id == *id).unwrap();
+                let movie = movies[id];
+                let mut lines = vec![if watchlist.contains(*id) {
+                    Line::from(format!("{} · {}", title(movie), t("feedback.on_watchlist")))
+                } else {
+                    Line::from(title(movie))
+                }];
 
-                movie
-                    .original_title
-                    .as_deref()
-                    .unwrap_or(&movie.primary_title)
+                let because: Vec<_> = reasons
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| movies.get(id).copied())
+                    .map(title)
+                    .collect();
+                if !because.is_empty() {
+                    let reason = tr!(reason_key, movies = because.join(", "));
+                    lines.push(Line::from(format!("  ↳ {reason}")).style(reason_style));
+                }
+                ListItem::new(lines)
             }))
             .highlight_style(highlight_style)
         };
 
-        let top_text = Text::raw("Rekomendacje").centered();
-        let top_list = list_from_ids(top_recs);
+        let top_text = Text::raw(t("recs.top")).centered();
+        let top_list = list_from_ids(top_recs, "recs.because_liked");
         let mut top_state = ListState::default();
 
-        let anti_text = Text::raw("Anty-rekomendacje").centered();
-        let anti_list = list_from_ids(anti_recs);
+        let anti_text = Text::raw(t("recs.anti")).centered();
+        let anti_list = list_from_ids(anti_recs, "recs.because_disliked");
         let mut anti_state = ListState::default();
 
         if anti_recs_hovered {
@@ -239,67 +1086,42 @@ impl<'a> App<'a> {
             top_state.select(Some(hovered));
         }
 
-        let selected_movie = {
-            let set = if anti_recs_hovered {
-                anti_recs
-            } else {
-                top_recs
-            };
-            let id = set[hovered];
-            movies.iter().find(|m| m.id == id).unwrap()
+        let set = if anti_recs_hovered {
+            anti_recs
+        } else {
+            top_recs
         };
+        let find = |i: usize| movies.get(set.get(i)?).copied();
+        self.thumbnails.prefetch(find, hovered);
 
-        let movie_title = Text::raw(
-            selected_movie
-                .original_title
-                .as_deref()
-                .unwrap_or(&selected_movie.primary_title),
-        )
-        .centered();
-
-        if let Some(movie_thumb) = App::thumb_path(selected_movie, cache) {
-            let v = Layout::vertical([Constraint::Percentage(60), Constraint::Fill(1)]);
-            let [image_area, new_right] = v.areas(right);
-            right = new_right;
-
-            #[cfg(not(target_os = "windows"))]
-            let picker = Picker::from_query_stdio().unwrap();
-            #[cfg(target_os = "windows")]
-            let picker = Picker::from_fontsize((8, 12));
-            let img = ImageReader::open(movie_thumb).unwrap().decode().unwrap();
-            let mut img = picker.new_resize_protocol(img);
-            frame.render_stateful_widget(
-                StatefulImage::new().resize(Resize::Scale(None)),
-                image_area,
-                &mut img,
-            );
-        }
-
-        let directors: Vec<_> = selected_movie
-            .directors
-            .iter()
-            .map(|d| d.display_name.as_str())
-            .collect();
-        let directors = if directors.is_empty() {
-            "BRAK INFORMACJI".to_string()
-        } else {
-            directors.join(", ")
+        let filter_line = match editing {
+            Some(text) => Line::from(tr!("filter.editing", query = text)),
+            None => {
+                let query = if options.filter.query.is_empty() {
+                    t("filter.none")
+                } else {
+                    &options.filter.query
+                };
+                let mut text = tr!(
+                    "filter.status",
+                    query = query,
+                    count = options.count,
+                    diversity = format!("{:.1}", options.diversity)
+                );
+                if options.filter.new_directors {
+                    text = format!("{text} · {}", t("filter.new_directors"));
+                }
+                Line::from(format!("{text} · {}", t("filter.keys")))
+            }
         };
-        let movie_info = format!(
-            r#"{desc}
-
-Reżyseria: {directors}
-Rok premiery: {year}"#,
-            desc = selected_movie.plot.as_deref().unwrap_or("BRAK OPISU"),
-            year = selected_movie
-                .start_year
-                .map_or_else(|| "NIEZNANY".to_string(), |year| year.to_string())
-        );
-        let movie_info = Text::raw(movie_info);
-        let movie_info = Paragraph::new(movie_info).wrap(Wrap { trim: true });
 
-        let help_text =
-            Text::raw("Poruszaj się używając strzałek. Przejdź do drugiej sekcji używając [Tab]");
+        let help_text = match message {
+            Some(message) => message.to_string(),
+            None if editing.is_some() => t("filter.help").to_string(),
+            None if *unsaved => format!("{} {}", t("recs.help"), t("recs.save_help")),
+            None => t("recs.help").to_string(),
+        };
+        let help_text = Text::raw(help_text);
 
         frame.render_widget(
             top_text,
@@ -339,57 +1161,281 @@ Rok premiery: {year}"#,
             },
             &mut anti_state,
         );
-        frame.render_widget(movie_title, title_area);
-        frame.render_widget(movie_info, right);
+        if let Some(movie) = find(hovered) {
+            App::render_movie(frame, &mut self.thumbnails, movie, right);
+        } else if top_recs.is_empty() {
+            frame.render_widget(Text::raw(t("filter.empty")).centered(), right);
+        }
+        frame.render_widget(filter_line.style(reason_style), filter_area);
         frame.render_widget(help_text, help);
     }
-}
 
-fn main() -> Result<()> {
-    color_eyre::install()?;
+    fn render_similar(&mut self, frame: &mut Frame) {
+        let AppState::SimilarMovies {
+            movie,
+            similar,
+            hovered,
+            ..
+        } = &self.state
+        else {
+            return;
+        };
+        let movies = &self.movie_index;
+        let find = |id: &str| movies[id];
 
-    let mut terminal = ratatui::init_with_options(TerminalOptions {
-        viewport: Viewport::Inline(24),
-    });
+        let v = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]);
+        let h = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]);
+        let [area, help] = v.areas(frame.area());
+        let [left, right] = h.areas(area);
+        let v = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [label, list_area] = v.areas(left);
+
+        let label_text = tr!("similar.title", movie = title(find(movie)));
+        let list = List::new(similar.iter().map(|&id| title(find(id))))
+            .highlight_style(Style::new().bg(Color::LightBlue).fg(Color::Black));
+        let mut state = ListState::default().with_selected(Some(*hovered));
+
+        frame.render_widget(Text::raw(label_text).centered(), label);
+        frame.render_stateful_widget(list, list_area, &mut state);
+        if let Some(&selected) = similar.get(*hovered) {
+            let nearby = |i: usize| similar.get(i).map(|id| find(id));
+            self.thumbnails.prefetch(nearby, *hovered);
+            App::render_movie(frame, &mut self.thumbnails, find(selected), right);
+        }
+        frame.render_widget(Text::raw(t("similar.help")), help);
+    }
 
-    let movies: Vec<ImdbTitle> = serde_json::from_str(MOVIES_STR)?;
-    let users: Vec<User> = serde_json::from_str(USERS_STR)?;
-    let extra_users: Vec<User> = serde_json::from_str(EXTRA_USERS_STR)?;
-    let mut movie_ratings = HashMap::<&str, Vec<(&str, f32)>>::new();
+    fn render_neighbours(
+        frame: &mut Frame,
+        users: &[User],
+        user: usize,
+        neighbours: &[Neighbour],
+        hovered: usize,
+    ) {
+        let v = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ]);
+        let [label, list_area, help] = v.areas(frame.area());
 
-    for (user, rating) in users.iter().chain(&extra_users).flat_map(|user| {
-        user.ratings
+        let label_text = tr!("neighbours.title", user = users[user].name);
+        let list = List::new(neighbours.iter().map(|neighbour| {
+            let mut text = tr!(
+                "neighbours.entry",
+                user = users[neighbour.user].name,
+                similarity = format!("{:.2}", neighbour.similarity),
+                overlap = neighbour.overlap
+            );
+            if let Some(agreement) = neighbour.agreement {
+                let agreement = format!("{:.0}", agreement * 100.);
+                text = format!(
+                    "{text} · {}",
+                    tr!("neighbours.agreement", agreement = agreement)
+                );
+            }
+            text
+        }))
+        .highlight_style(Style::new().bg(Color::LightBlue).fg(Color::Black));
+        let mut state = ListState::default().with_selected(Some(hovered));
+
+        frame.render_widget(Text::raw(label_text).centered(), label);
+        if neighbours.is_empty() {
+            frame.render_widget(Text::raw(t("neighbours.empty")).centered(), list_area);
+        }
+        frame.render_stateful_widget(list, list_area, &mut state);
+        frame.render_widget(Text::raw(t("neighbours.help")), help);
+    }
+
+    fn render_ratings(&mut self, frame: &mut Frame) {
+        let AppState::UserRatings {
+            user,
+            viewer,
+            ratings,
+            hovered,
+            ..
+        } = &self.state
+        else {
+            return;
+        };
+        let movies = &self.movie_index;
+        let find = |id: &str| movies[id];
+        let viewer_ratings: HashMap<&str, u32> = self.users[*viewer]
+            .ratings
             .iter()
-            .map(|rating| (user.name.as_str(), rating))
-    }) {
-        movie_ratings
-            .entry(&rating.movie)
-            .or_default()
-            .push((user, rating.rating as f32));
-    }
-
-    let mut set = Dataset::new();
-    for (movie, ratings) in movie_ratings {
-        // Skip outliers from the ratings
-        if ratings.len() == 1 && matches!(ratings[0].1, 1. | 10.) {
-            continue;
-        } else {
-            for (user, rating) in ratings {
-                set.push(user, movie, rating);
+            .map(|rating| (rating.movie.as_str(), rating.rating))
+            .collect();
+
+        let v = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]);
+        let h = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]);
+        let [area, help] = v.areas(frame.area());
+        let [left, right] = h.areas(area);
+        let v = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [label, list_area] = v.areas(left);
+
+        let label_text = tr!("ratings.title", user = self.users[*user].name);
+        let list = List::new(ratings.iter().map(|&(id, rating)| {
+            let mut text = tr!("ratings.entry", movie = title(find(id)), rating = rating);
+            if let Some(yours) = viewer_ratings.get(id) {
+                text = format!("{text} · {}", tr!("ratings.yours", rating = yours));
             }
+            text
+        }))
+        .highlight_style(Style::new().bg(Color::LightBlue).fg(Color::Black));
+        let mut state = ListState::default().with_selected(Some(*hovered));
+
+        frame.render_widget(Text::raw(label_text).centered(), label);
+        frame.render_stateful_widget(list, list_area, &mut state);
+        if let Some(&(selected, _)) = ratings.get(*hovered) {
+            let nearby = |i: usize| ratings.get(i).map(|&(id, _)| find(id));
+            self.thumbnails.prefetch(nearby, *hovered);
+            App::render_movie(frame, &mut self.thumbnails, find(selected), right);
         }
+        frame.render_widget(Text::raw(t("ratings.help")), help);
     }
 
-    let iterations = var("REC_ITERATIONS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(ITERATIONS);
-    let factors = var("REC_FACTORS")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(FACTORS);
+    fn render_group(&mut self, frame: &mut Frame) {
+        let AppState::GroupRecs {
+            members,
+            aggregation,
+            recs,
+            hovered,
+        } = &self.state
+        else {
+            return;
+        };
+        let movies = &self.movie_index;
+        let find = |id: &str| movies[id];
+        let names: Vec<_> = members
+            .iter()
+            .map(|&user| self.users[user].name.as_str())
+            .collect();
+
+        let v = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]);
+        let h = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]);
+        let [area, help] = v.areas(frame.area());
+        let [left, right] = h.areas(area);
+        let v = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(members.len() as u16 + 1),
+        ]);
+        let [label, list_area, predictions_area] = v.areas(left);
+
+        let label_text = Text::from(vec![
+            Line::from(tr!("group.title", users = names.join(", "))),
+            Line::from(tr!("group.strategy", strategy = aggregation.name())),
+        ])
+        .centered();
+        let list = List::new(
+            recs.iter()
+                .map(|rec| format!("{:.1} {}", rec.score, title(find(rec.movie)))),
+        )
+        .highlight_style(Style::new().bg(Color::LightBlue).fg(Color::Black));
+        let mut state = ListState::default().with_selected(Some(*hovered));
+
+        frame.render_widget(label_text, label);
+        frame.render_stateful_widget(list, list_area, &mut state);
+        match recs.get(*hovered) {
+            Some(rec) => {
+                let mut lines = vec![Line::from(t("group.predictions"))];
+                lines.extend(
+                    names
+                        .iter()
+                        .zip(&rec.predictions)
+                        .map(|(name, prediction)| {
+                            Line::from(tr!(
+                                "group.prediction",
+                                user = name,
+                                rating = format!("{prediction:.1}")
+                            ))
+                        }),
+                );
+                frame.render_widget(Text::from(lines), predictions_area);
+                let nearby = |i: usize| recs.get(i).map(|rec| find(rec.movie));
+                self.thumbnails.prefetch(nearby, *hovered);
+                App::render_movie(frame, &mut self.thumbnails, find(rec.movie), right);
+            }
+            None => frame.render_widget(Text::raw(t("group.empty")), list_area),
+        }
+        frame.render_widget(Text::raw(t("group.help")), help);
+    }
+
+    /// Renders the title, poster and details of a movie.
+    fn render_movie(frame: &mut Frame, thumbnails: &mut Thumbnails, movie: &ImdbTitle, area: Rect) {
+        let v = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [title_area, mut right] = v.areas(area);
+
+        let movie_title = Text::raw(title(movie)).centered();
 
-    let engine = std::thread::scope(|s| -> Result<_> {
+        if movie.primary_image.is_some() {
+            let v = Layout::vertical([Constraint::Percentage(60), Constraint::Fill(1)]);
+            let [image_area, new_right] = v.areas(right);
+            right = new_right;
+
+            // The space for the poster is kept while it loads, so the details don't jump around.
+            let placeholder = match thumbnails.get(movie) {
+                Poster::Ready(image) => {
+                    frame.render_stateful_widget(
+                        StatefulImage::new().resize(Resize::Scale(None)),
+                        image_area,
+                        image,
+                    );
+                    None
+                }
+                Poster::Loading => Some(t("movie.poster_loading")),
+                Poster::Unavailable => Some(t("movie.poster_unavailable")),
+            };
+            if let Some(placeholder) = placeholder {
+                let placeholder =
+                    Text::raw(placeholder).style(Style::new().add_modifier(Modifier::DIM));
+                frame.render_widget(placeholder.centered(), image_area);
+            }
+        }
+
+        let directors: Vec<_> = movie
+            .directors
+            .iter()
+            .map(|d| d.display_name.as_str())
+            .collect();
+        let directors = if directors.is_empty() {
+            t("movie.no_directors").to_string()
+        } else {
+            directors.join(", ")
+        };
+        let year = movie.start_year.map_or_else(
+            || t("movie.unknown_year").to_string(),
+            |year| year.to_string(),
+        );
+        let movie_info = format!(
+            "{desc}\n\n{directors}\n{year}",
+            desc = movie.plot.as_deref().unwrap_or(t("movie.no_plot")),
+            directors = tr!("movie.directors", directors = directors),
+            year = tr!("movie.year", year = year),
+        );
+        let movie_info = Text::raw(movie_info);
+        let movie_info = Paragraph::new(movie_info).wrap(Wrap { trim: true });
+
+        frame.render_widget(movie_title, title_area);
+        frame.render_widget(movie_info, right);
+    }
+}
+
+/// Returns the title of the movie to show, preferring the original one.
+fn title(movie: &ImdbTitle) -> &str {
+    movie
+        .original_title
+        .as_deref()
+        .unwrap_or(&movie.primary_title)
+}
+
+/// Trains the model, showing the progress of the training.
+fn train(
+    terminal: &mut DefaultTerminal,
+    ratings: &[(&str, &str, f32)],
+    params: Hyperparams,
+) -> Result<Model> {
+    std::thread::scope(|s| -> Result<_> {
         enum Event {
             Tick,
             End,
@@ -398,11 +1444,7 @@ fn main() -> Result<()> {
         let (tx, rx) = mpsc::channel();
 
         let engine = s.spawn(move || {
-            let result = RecommenderBuilder::new()
-                .iterations(iterations)
-                .factors(factors)
-                .callback(|_info| _ = tx.send(Event::Tick))
-                .fit_explicit(&set);
+            let result = Model::train(ratings, params, |_info| _ = tx.send(Event::Tick));
             tx.send(Event::End).ok();
             result
         });
@@ -411,10 +1453,10 @@ fn main() -> Result<()> {
         loop {
             terminal.draw(|frame| {
                 let area = frame.area();
-                let label = Text::raw("Ładowanie silnika rekomendacji").centered();
+                let label = Text::raw(t("loading")).centered();
                 let bar = Gauge::default()
                     .gauge_style(Style::default().fg(Color::Yellow).bg(Color::Black))
-                    .ratio(progress / iterations as f64);
+                    .ratio(progress / params.iterations as f64);
                 frame.render_widget(
                     label,
                     Rect {
@@ -444,26 +1486,154 @@ fn main() -> Result<()> {
         }
 
         Ok(engine.join().unwrap())
-    })?;
+    })
+}
 
+/// Trains the recommendation engine, or loads the saved model.
+/// The progress of the training is shown in the terminal, if there is one.
+fn load_engine(
+    algorithm: Algorithm,
+    ratings: &[(&str, &str, f32)],
+    movies: &[ImdbTitle],
+    params: Hyperparams,
+    model_path: &Path,
+    mut terminal: Option<&mut DefaultTerminal>,
+) -> Result<Box<dyn Recommender>> {
+    // Only matrix factorization takes long enough to train to be worth saving.
+    let mut load_or_train = || -> Result<Model> {
+        if let Some(model) = Model::load(model_path, fingerprint(ratings, params)) {
+            return Ok(model);
+        }
+        let model = match terminal.as_deref_mut() {
+            Some(terminal) => train(terminal, ratings, params)?,
+            None => Model::train(ratings, params, |_| {}),
+        };
+        // The saved model only saves time on the next start, so failing to save it is fine.
+        model.save(model_path).ok();
+        Ok(model)
+    };
+    Ok(match algorithm {
+        Algorithm::MatrixFactorization => Box::new(load_or_train()?),
+        Algorithm::Hybrid => Box::new(Hybrid::new(load_or_train()?, Content::new(ratings, movies))),
+        other => other.train(ratings, movies, params),
+    })
+}
+
+fn bail_usage() -> ! {
+    eprintln!("{}", t("usage.rekomendacje"));
+    eprintln!("{}", t("usage.algorithms"));
+    std::process::exit(1);
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    i18n::init(rekomendacje::CATALOGS, Locale::from_args(&mut args)?);
+
+    let mut data_dir = var("REC_DATA_DIR").ok().map(PathBuf::from);
+    let mut algorithm = Algorithm::default();
+    let mut serve = None;
+    let mut export = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "export" => export = Some(export::Options::parse(args.by_ref())),
+            "serve" => serve = Some(DEFAULT_ADDR.to_string()),
+            "--addr" if serve.is_some() => {
+                serve = Some(args.next().unwrap_or_else(|| bail_usage()));
+            }
+            "--data" => data_dir = Some(args.next().unwrap_or_else(|| bail_usage()).into()),
+            "--algorithm" => {
+                let name = args.next().unwrap_or_else(|| bail_usage());
+                algorithm = name.parse().map_err(|e: String| eyre!(e))?;
+            }
+            _ => bail_usage(),
+        }
+    }
     let dirs = ProjectDirs::from("pj", "s28840", "rekomendacje").unwrap();
-    let thumb_cache = dirs.cache_dir();
+    // New users are saved to the user's data directory, unless another one was given,
+    // and once there are some, that data is used instead of the embedded one.
+    let data_dir = data_dir.or_else(|| {
+        let user_data = dirs.data_dir();
+        Data::exists(user_data).then(|| user_data.to_path_buf())
+    });
+    let save_dir = data_dir
+        .clone()
+        .unwrap_or_else(|| dirs.data_dir().to_path_buf());
+    let mut data = Data::load_or_embedded(data_dir.as_deref())?;
+    // Ratings given to recommendations since the data was last saved.
+    let feedback_path = save_dir.join(FEEDBACK_FILE);
+    let feedback = Feedback::load(&feedback_path)?;
+    feedback.merge_into(&mut data);
+
+    let params = Hyperparams::from_env();
+
+    let thumb_cache = dirs.cache_dir().join(THUMBNAILS_DIR);
+    let model_path = dirs.cache_dir().join(MODEL_FILE);
+
+    if let Some(options) = export {
+        eprintln!("{}", t("loading"));
+        let ratings = data.ratings();
+        let engine = load_engine(algorithm, &ratings, &data.movies, params, &model_path, None)?;
+        return export::run(&options, &data, engine.as_ref());
+    }
+
+    if let Some(addr) = serve {
+        eprintln!("{}", t("loading"));
+        let ratings = data.ratings();
+        let engine = load_engine(algorithm, &ratings, &data.movies, params, &model_path, None)?;
+        let server = Server::bind(&addr, data, engine, Some(save_dir))?;
+        if let Some(addr) = server.addr() {
+            eprintln!("{}", tr!("server.listening", addr = addr));
+        }
+        return server.run();
+    }
+
+    let mut terminal = ratatui::init_with_options(TerminalOptions {
+        viewport: Viewport::Inline(24),
+    });
+
+    let ratings = data.ratings();
+    let engine = load_engine(
+        algorithm,
+        &ratings,
+        &data.movies,
+        params,
+        &model_path,
+        Some(&mut terminal),
+    )?;
 
     let display_all_users = var("REC_DEBUG_ALL_USERS")
         .ok()
         .is_some_and(|v| v.trim() == "1");
     let users = if display_all_users {
-        users.iter().chain(extra_users.iter()).cloned().collect()
+        data.users
+            .iter()
+            .chain(&data.extra_users)
+            .cloned()
+            .collect()
     } else {
-        users.clone()
+        data.users.clone()
     };
 
+    let onboarding = movies_to_rate(&ratings, engine.as_ref(), ONBOARDING_MOVIES);
+
     let mut app = App {
-        movies: &movies,
-        users: &users,
-        thumb_cache,
+        data: &data,
+        movies: &data.movies,
+        movie_index: data.movie_index(),
+        users,
+        thumbnails: Thumbnails::new(thumb_cache),
+        save_dir: &save_dir,
+        feedback,
+        feedback_path,
+        onboarding,
         engine,
-        state: AppState::SelectingUser { hovered: 0 },
+        state: AppState::SelectingUser {
+            hovered: 0,
+            selected: Vec::new(),
+        },
     };
 
     app.run(terminal)?;
@@ -0,0 +1,470 @@
+//! The recommendation engine: a matrix factorization model trained with [`discorec`],
+//! which can be saved to disk and loaded back, so it doesn't have to be retrained on every start.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::Path,
+};
+
+use color_eyre::eyre::{Result, WrapErr};
+use discorec::{Dataset, FitInfo, RecommenderBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    preprocessing::{Preprocessing, Scale},
+    recommender::{Recommender, top},
+};
+
+/// Bumped whenever the saved format or the training changes, so old models get retrained.
+const FORMAT_VERSION: u32 = 5;
+
+/// How strongly the factors of users added with [`Model::add_user`] are pulled towards zero,
+/// so a handful of ratings doesn't give them extreme predictions.
+const FOLD_IN_REGULARIZATION: f64 = 0.1;
+
+/// Settings of the training.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hyperparams {
+    pub iterations: u32,
+    pub factors: u32,
+    /// How strongly the factors are pulled towards zero, to avoid overfitting.
+    pub regularization: f32,
+    pub preprocessing: Preprocessing,
+}
+
+impl Default for Hyperparams {
+    /// Defaults picked with the `evaluate` binary.
+    fn default() -> Self {
+        Hyperparams {
+            iterations: 256,
+            factors: 128,
+            regularization: 0.05,
+            preprocessing: Preprocessing::default(),
+        }
+    }
+}
+
+impl Hyperparams {
+    /// Returns the defaults, overridden by the `REC_ITERATIONS`, `REC_FACTORS`,
+    /// `REC_REGULARIZATION`, `REC_NORMALIZATION`, `REC_MIN_MOVIE_RATINGS`
+    /// and `REC_MIN_USER_RATINGS` environment variables.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok()?.parse().ok()
+        }
+        let defaults = Hyperparams::default();
+        let preprocessing = defaults.preprocessing;
+        Hyperparams {
+            iterations: var("REC_ITERATIONS").unwrap_or(defaults.iterations),
+            factors: var("REC_FACTORS").unwrap_or(defaults.factors),
+            regularization: var("REC_REGULARIZATION").unwrap_or(defaults.regularization),
+            preprocessing: Preprocessing {
+                normalization: var("REC_NORMALIZATION").unwrap_or(preprocessing.normalization),
+                min_movie_ratings: var("REC_MIN_MOVIE_RATINGS")
+                    .unwrap_or(preprocessing.min_movie_ratings),
+                min_user_ratings: var("REC_MIN_USER_RATINGS")
+                    .unwrap_or(preprocessing.min_user_ratings),
+            },
+        }
+    }
+}
+
+/// Returns a hash of everything the trained model depends on.
+///
+/// A saved model is only reused if its fingerprint matches. The hash may change between
+/// Rust versions, which only means the model is retrained once more.
+pub fn fingerprint(ratings: &[(&str, &str, f32)], params: Hyperparams) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    FORMAT_VERSION.hash(&mut hasher);
+    params.iterations.hash(&mut hasher);
+    params.factors.hash(&mut hasher);
+    params.regularization.to_bits().hash(&mut hasher);
+    params.preprocessing.hash(&mut hasher);
+    ratings.len().hash(&mut hasher);
+    for &(user, item, rating) in ratings {
+        user.hash(&mut hasher);
+        item.hash(&mut hasher);
+        rating.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Learned factors of all users and movies.
+///
+/// The predicted rating is the dot product of the user and movie factors, plus the mean
+/// of all ratings. [`discorec`] fits the dot product to the ratings as they are, so the mean
+/// is subtracted from them before the training.
+/// With a [`Normalization`](crate::preprocessing::Normalization), this is on the normalized scale, and mapped back to the user's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    fingerprint: u64,
+    /// How the ratings were preprocessed, which decides how predictions are mapped back.
+    preprocessing: Preprocessing,
+    /// Mean of the normalized ratings.
+    global_mean: f32,
+    /// Mean of the ratings before normalization.
+    rating_mean: f32,
+    users: Vec<String>,
+    items: Vec<String>,
+    user_factors: Vec<Vec<f32>>,
+    item_factors: Vec<Vec<f32>>,
+    /// Indices of the movies rated by each user, with the ratings.
+    /// These movies are left out of the user's recommendations.
+    rated: Vec<Vec<(usize, f32)>>,
+    #[serde(skip)]
+    user_index: HashMap<String, usize>,
+    #[serde(skip)]
+    item_index: HashMap<String, usize>,
+    /// Normalization of each user's ratings, computed from [`rated`](Model::rated).
+    #[serde(skip)]
+    scales: Vec<Scale>,
+}
+
+impl Model {
+    /// Trains a new model. `callback` is called after each iteration of the training.
+    pub fn train(
+        ratings: &[(&str, &str, f32)],
+        params: Hyperparams,
+        callback: impl Fn(FitInfo),
+    ) -> Model {
+        let preprocessing = params.preprocessing;
+        let training = preprocessing.filter(ratings);
+        let rating_mean =
+            training.iter().map(|&(_, _, rating)| rating).sum::<f32>() / training.len() as f32;
+
+        // Scales are computed from the ratings of the trained movies, the same ones
+        // kept in `rated`, so they're the same when the model is loaded.
+        let items: HashSet<&str> = training.iter().map(|&(_, item, _)| item).collect();
+        let mut by_user = HashMap::<&str, Vec<f32>>::new();
+        for &(user, item, rating) in ratings {
+            if items.contains(item) {
+                by_user.entry(user).or_default().push(rating);
+            }
+        }
+        let scales: HashMap<&str, Scale> = by_user
+            .into_iter()
+            .map(|(user, ratings)| {
+                let scale = preprocessing.normalization.scale(&ratings, rating_mean);
+                (user, scale)
+            })
+            .collect();
+
+        let normalized: Vec<_> = training
+            .iter()
+            .map(|&(user, item, rating)| (user, item, scales[user].normalize(rating)))
+            .collect();
+        let global_mean =
+            normalized.iter().map(|&(_, _, rating)| rating).sum::<f32>() / normalized.len() as f32;
+        let mut set = Dataset::new();
+        for &(user, item, rating) in &normalized {
+            set.push(user, item, rating - global_mean);
+        }
+        let recommender = RecommenderBuilder::new()
+            .iterations(params.iterations)
+            .factors(params.factors)
+            .regularization(params.regularization)
+            .callback(callback)
+            .fit_explicit(&set);
+
+        let users: Vec<String> = recommender
+            .user_ids()
+            .iter()
+            .map(|u| u.to_string())
+            .collect();
+        let items: Vec<String> = recommender
+            .item_ids()
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        let user_factors = recommender
+            .user_ids()
+            .iter()
+            .map(|u| recommender.user_factors(u).unwrap_or_default().to_vec())
+            .collect();
+        let item_factors = recommender
+            .item_ids()
+            .iter()
+            .map(|i| recommender.item_factors(i).unwrap_or_default().to_vec())
+            .collect();
+
+        let mut model = Model {
+            fingerprint: fingerprint(ratings, params),
+            preprocessing,
+            global_mean,
+            rating_mean,
+            rated: vec![Vec::new(); users.len()],
+            users,
+            items,
+            user_factors,
+            item_factors,
+            user_index: HashMap::new(),
+            item_index: HashMap::new(),
+            scales: Vec::new(),
+        };
+        model.build_index();
+        // Users left out of the training are added the same way as new users.
+        let mut left_out = BTreeMap::<&str, Vec<(&str, f32)>>::new();
+        for &(user, item, rating) in ratings {
+            match (model.user(user), model.item(item)) {
+                (Some(u), Some(i)) => model.rated[u].push((i, rating)),
+                (None, _) => left_out.entry(user).or_default().push((item, rating)),
+                (Some(_), None) => {}
+            }
+        }
+        model.build_scales();
+        for (user, ratings) in left_out {
+            model.add_user(user, &ratings);
+        }
+        model
+    }
+
+    /// Loads a model saved with [`save`](Model::save), if it was trained with the same
+    /// ratings and hyperparameters. Returns `None` if it wasn't, or it can't be read.
+    pub fn load(path: &Path, fingerprint: u64) -> Option<Model> {
+        let text = fs::read_to_string(path).ok()?;
+        let mut model: Model = serde_json::from_str(&text).ok()?;
+        if model.fingerprint != fingerprint {
+            return None;
+        }
+        model.build_index();
+        model.build_scales();
+        Some(model)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string(self)?;
+        fs::write(path, text).wrap_err_with(|| format!("failed to save the model to {path:?}"))
+    }
+
+    fn build_index(&mut self) {
+        self.user_index = self.users.iter().cloned().zip(0..).collect();
+        self.item_index = self.items.iter().cloned().zip(0..).collect();
+    }
+
+    fn build_scales(&mut self) {
+        self.scales = self.rated.iter().map(|rated| self.scale(rated)).collect();
+    }
+
+    fn scale(&self, rated: &[(usize, f32)]) -> Scale {
+        let ratings: Vec<f32> = rated.iter().map(|&(_, rating)| rating).collect();
+        self.preprocessing
+            .normalization
+            .scale(&ratings, self.rating_mean)
+    }
+
+    fn user(&self, user: &str) -> Option<usize> {
+        self.user_index.get(user).copied()
+    }
+
+    fn item(&self, item: &str) -> Option<usize> {
+        self.item_index.get(item).copied()
+    }
+
+    /// Solves `(VᵀV + λI) u = Vᵀ(r - μ)` for the user factors `u`, where `V` are the factors
+    /// of the rated movies, `r` the normalized ratings and `μ` the mean of all of them.
+    fn fold_in(&self, rated: &[(usize, f32)], scale: &Scale) -> Vec<f32> {
+        let mut b = vec![0f64; self.factor_count()];
+        for &(i, rating) in rated {
+            let residual = (scale.normalize(rating) - self.global_mean) as f64;
+            for (b, &v) in b.iter_mut().zip(&self.item_factors[i]) {
+                *b += v as f64 * residual;
+            }
+        }
+        solve_positive_definite(self.gram(rated), b)
+            .into_iter()
+            .map(|x| x as f32)
+            .collect()
+    }
+
+    fn factor_count(&self) -> usize {
+        self.item_factors.first().map_or(0, Vec::len)
+    }
+
+    /// Returns `VᵀV + λI`, where `V` are the factors of the rated movies.
+    fn gram(&self, rated: &[(usize, f32)]) -> Vec<Vec<f64>> {
+        let k = self.factor_count();
+        let mut a = vec![vec![0f64; k]; k];
+        for (i, row) in a.iter_mut().enumerate() {
+            row[i] = FOLD_IN_REGULARIZATION;
+        }
+        for &(i, _) in rated {
+            let v = &self.item_factors[i];
+            for (row, &x) in a.iter_mut().zip(v) {
+                for (cell, &y) in row.iter_mut().zip(v) {
+                    *cell += x as f64 * y as f64;
+                }
+            }
+        }
+        a
+    }
+}
+
+impl Recommender for Model {
+    fn has_user(&self, user: &str) -> bool {
+        self.user_index.contains_key(user)
+    }
+
+    /// Adds a user who wasn't part of the training, or replaces one with the same name.
+    ///
+    /// The factors of the movies stay the same, and the user's factors are the ones which
+    /// best explain their ratings (a single step of alternating least squares).
+    /// This is much faster than retraining, but the new ratings don't affect other users.
+    fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]) {
+        let rated: Vec<(usize, f32)> = ratings
+            .iter()
+            .filter_map(|&(item, rating)| Some((self.item(item)?, rating)))
+            .collect();
+        let scale = self.scale(&rated);
+        let factors = self.fold_in(&rated, &scale);
+
+        match self.user(name) {
+            Some(u) => {
+                self.user_factors[u] = factors;
+                self.rated[u] = rated;
+                self.scales[u] = scale;
+            }
+            None => {
+                self.user_index.insert(name.to_string(), self.users.len());
+                self.users.push(name.to_string());
+                self.user_factors.push(factors);
+                self.rated.push(rated);
+                self.scales.push(scale);
+            }
+        }
+    }
+
+    /// The user's factors are treated as they would be computed by [`add_user`](Model::add_user),
+    /// a weighted sum over the rated movies, so for users from the training this is only
+    /// an approximation. A movie contributes more the more similar it is to `item`,
+    /// and the further from the mean its rating is.
+    fn explain(&self, user: &str, item: &str) -> Vec<(&str, f32)> {
+        let (Some(u), Some(j)) = (self.user(user), self.item(item)) else {
+            return Vec::new();
+        };
+        let (rated, scale) = (&self.rated[u], &self.scales[u]);
+        let target = self.item_factors[j].iter().map(|&x| x as f64).collect();
+        let weights = solve_positive_definite(self.gram(rated), target);
+
+        let mut contributions: Vec<_> = rated
+            .iter()
+            .map(|&(i, rating)| {
+                let similarity: f64 = weights
+                    .iter()
+                    .zip(&self.item_factors[i])
+                    .map(|(w, &v)| w * v as f64)
+                    .sum();
+                let residual = scale.normalize(rating) - self.global_mean;
+                let contribution = (residual * scale.slope()) as f64 * similarity;
+                (self.items[i].as_str(), contribution as f32)
+            })
+            .collect();
+        contributions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        contributions
+    }
+
+    /// Movies are compared by cosine similarity of their factors.
+    fn item_similarity(&self, a: &str, b: &str) -> Option<f32> {
+        let (a, b) = (self.item(a)?, self.item(b)?);
+        Some(cosine(&self.item_factors[a], &self.item_factors[b]))
+    }
+
+    /// Returns the mean of all ratings if either of them is unknown.
+    fn predict(&self, user: &str, item: &str) -> f32 {
+        match (self.user(user), self.item(item)) {
+            (Some(u), Some(i)) => self.scales[u]
+                .denormalize(self.global_mean + dot(&self.user_factors[u], &self.item_factors[i])),
+            _ => self.rating_mean,
+        }
+    }
+
+    fn user_recs(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(u) = self.user(user) else {
+            return Vec::new();
+        };
+        let (factors, scale) = (&self.user_factors[u], &self.scales[u]);
+        let rated: HashSet<usize> = self.rated[u].iter().map(|&(i, _)| i).collect();
+        let mut recs: Vec<_> = (0..self.items.len())
+            .filter(|i| !rated.contains(i))
+            .map(|i| {
+                let score =
+                    scale.denormalize(self.global_mean + dot(factors, &self.item_factors[i]));
+                (self.items[i].as_str(), score)
+            })
+            .collect();
+        top(&mut recs, count);
+        recs
+    }
+
+    /// Movies are compared by cosine similarity of their factors.
+    fn item_recs(&self, item: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(i) = self.item(item) else {
+            return Vec::new();
+        };
+        similar(&self.item_factors, i, &self.items, count)
+    }
+
+    /// Users are compared by cosine similarity of their factors.
+    fn similar_users(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        let Some(u) = self.user(user) else {
+            return Vec::new();
+        };
+        similar(&self.user_factors, u, &self.users, count)
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    dot(a, b) / (norm(a) * norm(b)).max(f32::EPSILON)
+}
+
+/// Solves `a x = b` using the Cholesky decomposition, for a symmetric positive-definite `a`.
+fn solve_positive_definite(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+    // Decompose in place into a lower triangular `l`, such that `a = l lᵀ`.
+    for j in 0..n {
+        let diagonal = a[j][j] - (0..j).map(|k| a[j][k] * a[j][k]).sum::<f64>();
+        a[j][j] = diagonal.max(f64::EPSILON).sqrt();
+        for i in (j + 1)..n {
+            let sum = a[i][j] - (0..j).map(|k| a[i][k] * a[j][k]).sum::<f64>();
+            a[i][j] = sum / a[j][j];
+        }
+    }
+    // Forward substitution, `l y = b`.
+    for i in 0..n {
+        b[i] = (b[i] - (0..i).map(|k| a[i][k] * b[k]).sum::<f64>()) / a[i][i];
+    }
+    // Back substitution, `lᵀ x = y`.
+    for i in (0..n).rev() {
+        b[i] = (b[i] - ((i + 1)..n).map(|k| a[k][i] * b[k]).sum::<f64>()) / a[i][i];
+    }
+    b
+}
+
+fn similar<'a>(
+    factors: &[Vec<f32>],
+    index: usize,
+    ids: &'a [String],
+    count: usize,
+) -> Vec<(&'a str, f32)> {
+    let target = &factors[index];
+    let mut result: Vec<_> = factors
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != index)
+        .map(|(i, other)| (ids[i].as_str(), cosine(target, other)))
+        .collect();
+    top(&mut result, count);
+    result
+}
@@ -0,0 +1,105 @@
+//! Users with tastes similar to a given one, and how much they agree on the movies they both rated.
+
+use std::collections::HashMap;
+
+use crate::{User, recommender::Recommender};
+
+/// Two ratings of the same movie at most this far apart count as agreeing.
+const AGREEMENT_MARGIN: u32 = 1;
+
+/// A user with tastes similar to another one.
+#[derive(Debug, Clone)]
+pub struct Neighbour {
+    /// Index of the user, in the slice passed to [`neighbours`].
+    pub user: usize,
+    /// How similar the tastes are, from -1 to 1.
+    pub similarity: f32,
+    /// Number of movies both users rated.
+    pub overlap: usize,
+    /// Part of the movies both users rated, which they rated about the same,
+    /// see [`AGREEMENT_MARGIN`]. `None` without common movies.
+    pub agreement: Option<f32>,
+}
+
+/// Returns up to `count` users most similar to `users[user]`, from the most similar one.
+///
+/// Models which learn something about the users, like the factors of matrix factorization,
+/// decide which users are similar. Otherwise the users are compared by their ratings.
+pub fn neighbours(
+    engine: &dyn Recommender,
+    users: &[User],
+    user: usize,
+    count: usize,
+) -> Vec<Neighbour> {
+    let index: HashMap<&str, usize> = users
+        .iter()
+        .enumerate()
+        .map(|(i, user)| (user.name.as_str(), i))
+        .collect();
+    // The engine also knows users who are only used for training, and aren't in `users`,
+    // so all of them are asked for, to still get `count` after filtering them out.
+    let mut similar: Vec<(usize, f32)> = engine
+        .similar_users(&users[user].name, usize::MAX)
+        .iter()
+        .filter_map(|&(name, similarity)| Some((*index.get(name)?, similarity)))
+        .filter(|&(other, _)| other != user)
+        .take(count)
+        .collect();
+    if similar.is_empty() {
+        similar = (0..users.len())
+            .filter(|&other| other != user)
+            .filter_map(|other| Some((other, rating_similarity(&users[user], &users[other])?)))
+            .collect();
+        similar.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        similar.truncate(count);
+    }
+
+    similar
+        .into_iter()
+        .map(|(other, similarity)| {
+            let common: Vec<_> = common(&users[user], &users[other]).collect();
+            let overlap = common.len();
+            let agreeing = common
+                .iter()
+                .filter(|(a, b)| a.abs_diff(*b) <= AGREEMENT_MARGIN)
+                .count();
+            Neighbour {
+                user: other,
+                similarity,
+                overlap,
+                agreement: (overlap > 0).then(|| agreeing as f32 / overlap as f32),
+            }
+        })
+        .collect()
+}
+
+/// Returns the ratings of the movies both users rated, as `(a's rating, b's rating)`.
+fn common<'a>(a: &'a User, b: &'a User) -> impl Iterator<Item = (u32, u32)> + 'a {
+    let b: HashMap<&str, u32> = b
+        .ratings
+        .iter()
+        .map(|rating| (rating.movie.as_str(), rating.rating))
+        .collect();
+    a.ratings
+        .iter()
+        .filter_map(move |rating| Some((rating.rating, *b.get(rating.movie.as_str())?)))
+}
+
+/// Pearson correlation of the ratings of the movies both users rated,
+/// or `None` if there are fewer than two of them.
+fn rating_similarity(a: &User, b: &User) -> Option<f32> {
+    let pairs: Vec<(f32, f32)> = common(a, b).map(|(a, b)| (a as f32, b as f32)).collect();
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f32;
+    let mean_a = pairs.iter().map(|(a, _)| a).sum::<f32>() / n;
+    let mean_b = pairs.iter().map(|(_, b)| b).sum::<f32>() / n;
+    let (mut covariance, mut variance_a, mut variance_b) = (0., 0., 0.);
+    for (a, b) in pairs {
+        covariance += (a - mean_a) * (b - mean_b);
+        variance_a += (a - mean_a).powi(2);
+        variance_b += (b - mean_b).powi(2);
+    }
+    Some(covariance / (variance_a * variance_b).sqrt().max(f32::EPSILON))
+}
@@ -0,0 +1,47 @@
+//! Picking movies for a new user to rate, so the engine can learn their taste.
+
+use std::collections::HashMap;
+
+use crate::recommender::Recommender;
+
+/// Movies more similar than this to one picked before are skipped,
+/// so the picks aren't all of the same kind.
+const MAX_SIMILARITY: f32 = 0.5;
+
+/// Returns up to `count` movies for a new user to rate.
+///
+/// Movies rated by more people come first, since a new user has most likely seen them too.
+/// Movies too similar to the ones picked before are skipped, unless there aren't enough others.
+pub fn movies_to_rate<'a>(
+    ratings: &[(&'a str, &'a str, f32)],
+    model: &dyn Recommender,
+    count: usize,
+) -> Vec<&'a str> {
+    let mut popularity = HashMap::<&str, usize>::new();
+    for &(_, movie, _) in ratings {
+        *popularity.entry(movie).or_default() += 1;
+    }
+    let mut popular: Vec<_> = popularity.into_iter().collect();
+    popular.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut picked: Vec<&str> = Vec::with_capacity(count);
+    let mut skipped = Vec::new();
+    for (movie, _) in popular {
+        if picked.len() == count {
+            break;
+        }
+        let too_similar = picked.iter().any(|other| {
+            model
+                .item_similarity(movie, other)
+                .is_some_and(|similarity| similarity > MAX_SIMILARITY)
+        });
+        if too_similar {
+            skipped.push(movie);
+        } else {
+            picked.push(movie);
+        }
+    }
+    let missing = count.saturating_sub(picked.len());
+    picked.extend(skipped.into_iter().take(missing));
+    picked
+}
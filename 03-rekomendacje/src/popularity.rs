@@ -0,0 +1,87 @@
+//! A baseline recommending the same best rated movies to everyone.
+
+use crate::recommender::{RatingMatrix, Recommender, top};
+
+/// The score of a movie is its mean rating, as if it also had this many ratings equal to
+/// the mean of all ratings, so a single 10 doesn't put a movie at the top.
+const PRIOR_RATINGS: f32 = 5.;
+
+#[derive(Debug, Clone)]
+pub struct Popularity {
+    matrix: RatingMatrix,
+    /// Score of each movie.
+    scores: Vec<f32>,
+}
+
+impl Popularity {
+    pub fn new(ratings: &[(&str, &str, f32)]) -> Popularity {
+        let mut popularity = Popularity {
+            matrix: RatingMatrix::new(ratings),
+            scores: Vec::new(),
+        };
+        popularity.compute_scores();
+        popularity
+    }
+
+    fn compute_scores(&mut self) {
+        let prior = self.matrix.global_mean * PRIOR_RATINGS;
+        self.scores = self
+            .matrix
+            .by_item
+            .iter()
+            .map(|ratings| {
+                let sum: f32 = ratings.iter().map(|&(_, r)| r).sum();
+                (sum + prior) / (ratings.len() as f32 + PRIOR_RATINGS)
+            })
+            .collect();
+    }
+}
+
+impl Recommender for Popularity {
+    fn has_user(&self, user: &str) -> bool {
+        self.matrix.user(user).is_some()
+    }
+
+    fn predict(&self, _user: &str, movie: &str) -> f32 {
+        match self.matrix.item(movie) {
+            Some(i) => self.scores[i],
+            None => self.matrix.global_mean,
+        }
+    }
+
+    /// Unknown users get the best movies overall.
+    fn user_recs(&self, user: &str, count: usize) -> Vec<(&str, f32)> {
+        match self.matrix.user(user) {
+            Some(u) => self.matrix.recommend(u, count, |i| self.scores[i]),
+            None => {
+                let mut recs: Vec<_> = self
+                    .matrix
+                    .items
+                    .iter()
+                    .map(String::as_str)
+                    .zip(self.scores.iter().copied())
+                    .collect();
+                top(&mut recs, count);
+                recs
+            }
+        }
+    }
+
+    /// Popularity says nothing about which movies are similar.
+    fn item_recs(&self, _movie: &str, _count: usize) -> Vec<(&str, f32)> {
+        Vec::new()
+    }
+
+    fn item_similarity(&self, _a: &str, _b: &str) -> Option<f32> {
+        None
+    }
+
+    fn similar_users(&self, _user: &str, _count: usize) -> Vec<(&str, f32)> {
+        Vec::new()
+    }
+
+    fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]) {
+        self.matrix.add_user(name, ratings);
+        self.compute_scores();
+    }
+}
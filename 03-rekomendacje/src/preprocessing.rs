@@ -0,0 +1,204 @@
+//! Cleaning and normalization of the ratings before matrix factorization.
+//!
+//! People use the 1-10 scale very differently: some give 8 to a movie they didn't like,
+//! others never go above 7. Normalizing each user's ratings lets the model learn
+//! what they liked, rather than how generous they are, and predictions are mapped
+//! back to the user's own scale.
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Standard deviations below this are raised to it, so a user who gave every movie
+/// almost the same rating doesn't get wildly scaled predictions.
+const MIN_DEVIATION: f32 = 0.5;
+
+/// How each user's ratings are mapped before the training.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Normalization {
+    /// Ratings are used as they are.
+    #[default]
+    None,
+    /// The user's mean rating is subtracted.
+    Mean,
+    /// The user's mean rating is subtracted, and the result divided by the standard deviation.
+    ZScore,
+    /// Ratings are replaced by their position among the user's ratings, from -1 for the lowest
+    /// to 1 for the highest, so only the order of the ratings matters.
+    Rank,
+}
+
+impl Normalization {
+    pub const ALL: [Normalization; 4] = [
+        Normalization::None,
+        Normalization::Mean,
+        Normalization::ZScore,
+        Normalization::Rank,
+    ];
+
+    /// Returns the mapping of one user's ratings. Users without any ratings are assumed
+    /// to rate like everyone else, with `global_mean` on average.
+    pub fn scale(self, ratings: &[f32], global_mean: f32) -> Scale {
+        let n = ratings.len() as f32;
+        let mean = if ratings.is_empty() {
+            global_mean
+        } else {
+            ratings.iter().sum::<f32>() / n
+        };
+        let deviation = if ratings.is_empty() {
+            1.
+        } else {
+            let variance = ratings.iter().map(|r| (r - mean).powi(2)).sum::<f32>() / n;
+            variance.sqrt().max(MIN_DEVIATION)
+        };
+        let mut sorted = ratings.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        Scale {
+            normalization: self,
+            mean,
+            deviation,
+            sorted,
+        }
+    }
+}
+
+impl Display for Normalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Normalization::None => write!(f, "none"),
+            Normalization::Mean => write!(f, "mean"),
+            Normalization::ZScore => write!(f, "zscore"),
+            Normalization::Rank => write!(f, "rank"),
+        }
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Normalization::None),
+            "mean" => Ok(Normalization::Mean),
+            "zscore" => Ok(Normalization::ZScore),
+            "rank" => Ok(Normalization::Rank),
+            _ => Err(format!(
+                "unknown normalization {s:?}, expected one of: none, mean, zscore, rank"
+            )),
+        }
+    }
+}
+
+/// A user's ratings mapped to the normalized scale, and predictions mapped back.
+#[derive(Debug, Clone)]
+pub struct Scale {
+    normalization: Normalization,
+    mean: f32,
+    deviation: f32,
+    /// The user's ratings, from the lowest.
+    sorted: Vec<f32>,
+}
+
+impl Scale {
+    pub fn normalize(&self, rating: f32) -> f32 {
+        match self.normalization {
+            Normalization::None => rating,
+            Normalization::Mean => rating - self.mean,
+            Normalization::ZScore => (rating - self.mean) / self.deviation,
+            Normalization::Rank => {
+                let n = self.sorted.len();
+                if n < 2 {
+                    return 0.;
+                }
+                let below = self.sorted.partition_point(|&r| r < rating);
+                let equal = self.sorted[below..].partition_point(|&r| r <= rating);
+                // Equal ratings share the middle of their positions.
+                let position = below as f32 + (equal as f32 - 1.) / 2.;
+                (2. * position / (n - 1) as f32 - 1.).clamp(-1., 1.)
+            }
+        }
+    }
+
+    /// Maps a value from the normalized scale back to a rating. For [`Normalization::Rank`],
+    /// this interpolates between the user's ratings, so predictions stay within their range.
+    pub fn denormalize(&self, value: f32) -> f32 {
+        match self.normalization {
+            Normalization::None => value,
+            Normalization::Mean => value + self.mean,
+            Normalization::ZScore => value * self.deviation + self.mean,
+            Normalization::Rank => {
+                let n = self.sorted.len();
+                if n < 2 {
+                    return self.sorted.first().copied().unwrap_or(self.mean);
+                }
+                let position = (value + 1.).clamp(0., 2.) / 2. * (n - 1) as f32;
+                let (low, high) = (position.floor() as usize, position.ceil() as usize);
+                let fraction = position - low as f32;
+                self.sorted[low] + (self.sorted[high] - self.sorted[low]) * fraction
+            }
+        }
+    }
+
+    /// Returns roughly how many rating points one unit of the normalized scale is worth.
+    pub fn slope(&self) -> f32 {
+        match self.normalization {
+            Normalization::None | Normalization::Mean => 1.,
+            Normalization::ZScore => self.deviation,
+            Normalization::Rank => match (self.sorted.first(), self.sorted.last()) {
+                (Some(low), Some(high)) => ((high - low) / 2.).max(MIN_DEVIATION),
+                _ => 1.,
+            },
+        }
+    }
+}
+
+/// Which ratings matrix factorization is trained on, and how they're normalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Preprocessing {
+    pub normalization: Normalization,
+    /// Movies with fewer ratings are left out of the training.
+    pub min_movie_ratings: usize,
+    /// Users with fewer ratings, after leaving out movies, are left out of the training.
+    /// They still get recommendations, computed from the factors of the movies they rated.
+    pub min_user_ratings: usize,
+}
+
+impl Default for Preprocessing {
+    fn default() -> Self {
+        Preprocessing {
+            normalization: Normalization::default(),
+            min_movie_ratings: 1,
+            min_user_ratings: 1,
+        }
+    }
+}
+
+impl Preprocessing {
+    /// Returns the ratings to train on, leaving out:
+    /// - movies rated only once, with 1 or 10. A single extreme rating says little
+    ///   about the movie, but pulls the factors of the user towards it,
+    /// - movies with fewer than [`min_movie_ratings`](Preprocessing::min_movie_ratings),
+    /// - then users with fewer than [`min_user_ratings`](Preprocessing::min_user_ratings).
+    pub fn filter<'a>(&self, ratings: &[(&'a str, &'a str, f32)]) -> Vec<(&'a str, &'a str, f32)> {
+        let mut movie_counts = HashMap::<&str, usize>::new();
+        for &(_, item, _) in ratings {
+            *movie_counts.entry(item).or_default() += 1;
+        }
+        let kept: Vec<_> = ratings
+            .iter()
+            .copied()
+            .filter(|&(_, item, rating)| {
+                let count = movie_counts[item];
+                !(count == 1 && matches!(rating, 1. | 10.)) && count >= self.min_movie_ratings
+            })
+            .collect();
+
+        let mut user_counts = HashMap::<&str, usize>::new();
+        for &(user, _, _) in &kept {
+            *user_counts.entry(user).or_default() += 1;
+        }
+        kept.into_iter()
+            .filter(|&(user, _, _)| user_counts[user] >= self.min_user_ratings)
+            .collect()
+    }
+}
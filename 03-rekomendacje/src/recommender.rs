@@ -0,0 +1,279 @@
+//! The common interface of all recommendation algorithms, and picking one of them.
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crate::{
+    ImdbTitle,
+    content::Content,
+    hybrid::Hybrid,
+    knn::{Knn, Neighbours, Similarity},
+    model::{Hyperparams, Model},
+    popularity::Popularity,
+};
+
+/// A recommendation algorithm, trained on `(user, movie, rating)` triples.
+///
+/// Movie and user ids are the same as in the training ratings.
+pub trait Recommender {
+    /// Returns whether the user is known to the model.
+    fn has_user(&self, user: &str) -> bool;
+
+    /// Predicts how the user would rate the movie.
+    fn predict(&self, user: &str, movie: &str) -> f32;
+
+    /// Returns up to `count` movies the user hasn't rated, with their predicted ratings,
+    /// from the best one.
+    fn user_recs(&self, user: &str, count: usize) -> Vec<(&str, f32)>;
+
+    /// Returns up to `count` movies most similar to the given one, with their similarity,
+    /// from the most similar one.
+    fn item_recs(&self, movie: &str, count: usize) -> Vec<(&str, f32)>;
+
+    /// Returns how similar two movies are, from -1 to 1,
+    /// or `None` if the model can't tell.
+    fn item_similarity(&self, a: &str, b: &str) -> Option<f32>;
+
+    /// Returns up to `count` users with tastes most similar to the given one,
+    /// from the most similar one.
+    fn similar_users(&self, user: &str, count: usize) -> Vec<(&str, f32)>;
+
+    /// Adds a user who wasn't part of the training, or replaces one with the same name.
+    fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]);
+
+    /// Splits the predicted rating of `movie` into contributions of the movies the user rated,
+    /// from the one which raised the prediction the most.
+    ///
+    /// Models which can't tell return an empty list.
+    fn explain(&self, user: &str, movie: &str) -> Vec<(&str, f32)> {
+        _ = (user, movie);
+        Vec::new()
+    }
+}
+
+/// The recommendation algorithms to pick from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// [`Model`], trained with matrix factorization.
+    #[default]
+    MatrixFactorization,
+    /// [`Knn`], predicting from the ratings of similar users or movies.
+    Knn(Neighbours, Similarity),
+    /// [`Popularity`], recommending the best rated movies to everyone.
+    Popularity,
+    /// [`Content`], recommending movies similar to the ones the user liked.
+    Content,
+    /// [`Hybrid`], a blend of matrix factorization and content.
+    Hybrid,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 8] = [
+        Algorithm::MatrixFactorization,
+        Algorithm::Knn(Neighbours::Users, Similarity::Cosine),
+        Algorithm::Knn(Neighbours::Users, Similarity::Pearson),
+        Algorithm::Knn(Neighbours::Items, Similarity::Cosine),
+        Algorithm::Knn(Neighbours::Items, Similarity::Pearson),
+        Algorithm::Popularity,
+        Algorithm::Content,
+        Algorithm::Hybrid,
+    ];
+
+    /// Returns whether the algorithm is affected by [`Hyperparams`].
+    pub fn uses_hyperparams(self) -> bool {
+        matches!(self, Algorithm::MatrixFactorization | Algorithm::Hybrid)
+    }
+
+    /// Trains the model. The movie details are only used by the content-based model.
+    pub fn train(
+        self,
+        ratings: &[(&str, &str, f32)],
+        movies: &[ImdbTitle],
+        params: Hyperparams,
+    ) -> Box<dyn Recommender> {
+        match self {
+            Algorithm::MatrixFactorization => Box::new(Model::train(ratings, params, |_| {})),
+            Algorithm::Knn(neighbours, similarity) => {
+                Box::new(Knn::new(ratings, neighbours, similarity))
+            }
+            Algorithm::Popularity => Box::new(Popularity::new(ratings)),
+            Algorithm::Content => Box::new(Content::new(ratings, movies)),
+            Algorithm::Hybrid => Box::new(Hybrid::new(
+                Model::train(ratings, params, |_| {}),
+                Content::new(ratings, movies),
+            )),
+        }
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Algorithm::MatrixFactorization => write!(f, "mf"),
+            Algorithm::Knn(neighbours, similarity) => {
+                let neighbours = match neighbours {
+                    Neighbours::Users => "user",
+                    Neighbours::Items => "item",
+                };
+                let similarity = match similarity {
+                    Similarity::Cosine => "cosine",
+                    Similarity::Pearson => "pearson",
+                };
+                write!(f, "{neighbours}-knn:{similarity}")
+            }
+            Algorithm::Popularity => write!(f, "popularity"),
+            Algorithm::Content => write!(f, "content"),
+            Algorithm::Hybrid => write!(f, "hybrid"),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    /// Parses an algorithm as shown by [`Display`], where the k-NN similarity is optional
+    /// and cosine by default.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, similarity) = match s.split_once(':') {
+            Some((name, "cosine")) => (name, Some(Similarity::Cosine)),
+            Some((name, "pearson")) => (name, Some(Similarity::Pearson)),
+            Some((_, other)) => return Err(format!("unknown similarity {other:?}")),
+            None => (s, None),
+        };
+        let similarity = similarity.unwrap_or(Similarity::Cosine);
+        match name {
+            "mf" => Ok(Algorithm::MatrixFactorization),
+            "user-knn" => Ok(Algorithm::Knn(Neighbours::Users, similarity)),
+            "item-knn" => Ok(Algorithm::Knn(Neighbours::Items, similarity)),
+            "popularity" => Ok(Algorithm::Popularity),
+            "content" => Ok(Algorithm::Content),
+            "hybrid" => Ok(Algorithm::Hybrid),
+            _ => Err(format!(
+                "unknown algorithm {s:?}, expected one of: mf, user-knn[:cosine|pearson], item-knn[:cosine|pearson], popularity, content, hybrid"
+            )),
+        }
+    }
+}
+
+/// Ratings stored both by user and by movie, for the models which work on the ratings directly.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RatingMatrix {
+    pub users: Vec<String>,
+    pub items: Vec<String>,
+    user_index: HashMap<String, usize>,
+    item_index: HashMap<String, usize>,
+    /// Ratings of each user, as `(item, rating)`, ordered by item.
+    pub by_user: Vec<Vec<(usize, f32)>>,
+    /// Ratings of each movie, as `(user, rating)`, ordered by user.
+    pub by_item: Vec<Vec<(usize, f32)>>,
+    pub global_mean: f32,
+}
+
+impl RatingMatrix {
+    pub fn new(ratings: &[(&str, &str, f32)]) -> Self {
+        let mut matrix = RatingMatrix::default();
+        for &(user, item, rating) in ratings {
+            let u = matrix.add_user_id(user);
+            let i = matrix.add_item(item);
+            matrix.by_user[u].push((i, rating));
+            matrix.by_item[i].push((u, rating));
+        }
+        matrix.finish();
+        matrix
+    }
+
+    /// Adds a movie without any ratings, and returns its index.
+    pub fn add_item(&mut self, item: &str) -> usize {
+        if let Some(&i) = self.item_index.get(item) {
+            return i;
+        }
+        self.items.push(item.to_string());
+        self.by_item.push(Vec::new());
+        self.item_index
+            .insert(item.to_string(), self.items.len() - 1);
+        self.items.len() - 1
+    }
+
+    fn add_user_id(&mut self, user: &str) -> usize {
+        if let Some(&u) = self.user_index.get(user) {
+            return u;
+        }
+        self.users.push(user.to_string());
+        self.by_user.push(Vec::new());
+        self.user_index
+            .insert(user.to_string(), self.users.len() - 1);
+        self.users.len() - 1
+    }
+
+    /// Adds a user, or replaces the ratings of one with the same name, and returns their index.
+    /// Ratings of unknown movies are ignored.
+    pub fn add_user(&mut self, name: &str, ratings: &[(&str, f32)]) -> usize {
+        let u = self.add_user_id(name);
+        for (i, _) in std::mem::take(&mut self.by_user[u]) {
+            self.by_item[i].retain(|&(other, _)| other != u);
+        }
+        for &(item, rating) in ratings {
+            if let Some(i) = self.item(item) {
+                self.by_user[u].push((i, rating));
+                self.by_item[i].push((u, rating));
+            }
+        }
+        self.finish();
+        u
+    }
+
+    fn finish(&mut self) {
+        for ratings in self.by_user.iter_mut().chain(&mut self.by_item) {
+            ratings.sort_by_key(|&(index, _)| index);
+        }
+        let (sum, count) = self
+            .by_user
+            .iter()
+            .flatten()
+            .fold((0., 0), |(sum, count), &(_, r)| (sum + r, count + 1));
+        self.global_mean = if count == 0 { 0. } else { sum / count as f32 };
+    }
+
+    pub fn user(&self, user: &str) -> Option<usize> {
+        self.user_index.get(user).copied()
+    }
+
+    pub fn item(&self, item: &str) -> Option<usize> {
+        self.item_index.get(item).copied()
+    }
+
+    /// Returns the rating the user gave the movie, if any.
+    pub fn rating(&self, u: usize, i: usize) -> Option<f32> {
+        let ratings = &self.by_user[u];
+        let index = ratings.binary_search_by_key(&i, |&(i, _)| i).ok()?;
+        Some(ratings[index].1)
+    }
+
+    /// Returns the mean of the ratings, or the mean of all ratings if there are none.
+    pub fn mean(&self, ratings: &[(usize, f32)]) -> f32 {
+        if ratings.is_empty() {
+            return self.global_mean;
+        }
+        ratings.iter().map(|&(_, r)| r).sum::<f32>() / ratings.len() as f32
+    }
+
+    /// Scores every movie the user hasn't rated, and returns the best `count` of them.
+    pub fn recommend(
+        &self,
+        u: usize,
+        count: usize,
+        score: impl Fn(usize) -> f32,
+    ) -> Vec<(&str, f32)> {
+        let mut recs: Vec<_> = (0..self.items.len())
+            .filter(|&i| self.rating(u, i).is_none())
+            .map(|i| (self.items[i].as_str(), score(i)))
+            .collect();
+        top(&mut recs, count);
+        recs
+    }
+}
+
+/// Sorts by descending score and keeps the first `count`.
+pub(crate) fn top(items: &mut Vec<(&str, f32)>, count: usize) {
+    items.sort_by(|a, b| b.1.total_cmp(&a.1));
+    items.truncate(count);
+}
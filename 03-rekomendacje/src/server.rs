@@ -0,0 +1,277 @@
+//! A local HTTP server answering with JSON, for tools which want recommendations
+//! without the terminal interface.
+//!
+//! | Request                       | Response                                            |
+//! |-------------------------------|-----------------------------------------------------|
+//! | `GET /users`                  | users, with the number of their ratings             |
+//! | `GET /movies`                 | all movies, in the same format as `movies.json`     |
+//! | `GET /users/{name}/recs`      | best movies for the user, with predicted ratings    |
+//! | `GET /users/{name}/anti-recs` | worst movies for the user, with predicted ratings   |
+//! | `GET /movies/{id}/similar`    | most similar movies, with their similarity          |
+//! | `POST /users/{name}/ratings`  | adds ratings, in the same format as in `users.json` |
+//!
+//! Lists take an optional `?count=N`, 10 by default. Errors are returned as
+//! `{"error": "message"}`, with a 4xx or 5xx status.
+
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf};
+
+use color_eyre::eyre::{Result, eyre};
+use serde_json::{Value, json};
+use tiny_http::{Header, Response};
+
+use crate::{ImdbTitle, Rating, User, data::Data, data::RATING_RANGE, recommender::Recommender};
+
+/// How many movies are listed, unless the request asks for another number.
+const DEFAULT_COUNT: usize = 10;
+
+/// An error response, with its HTTP status.
+struct Error(u16, String);
+
+impl Error {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Error(400, message.into())
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Error(404, message.into())
+    }
+}
+
+pub struct Server {
+    http: tiny_http::Server,
+    data: Data,
+    /// Users shown in the user list, including the ones added through the server.
+    users: Vec<User>,
+    engine: Box<dyn Recommender>,
+    /// Where submitted ratings are saved, if anywhere.
+    save_dir: Option<PathBuf>,
+    /// Position of each movie in `data.movies`, by id.
+    movie_index: HashMap<String, usize>,
+}
+
+impl Server {
+    /// Starts listening on `addr`. Port 0 picks any free port, see [`Server::addr`].
+    pub fn bind(
+        addr: &str,
+        data: Data,
+        engine: Box<dyn Recommender>,
+        save_dir: Option<PathBuf>,
+    ) -> Result<Server> {
+        let http =
+            tiny_http::Server::http(addr).map_err(|e| eyre!("failed to listen on {addr}: {e}"))?;
+        let movie_index = data
+            .movies
+            .iter()
+            .enumerate()
+            .map(|(i, movie)| (movie.id.clone(), i))
+            .collect();
+        Ok(Server {
+            http,
+            movie_index,
+            users: data.users.clone(),
+            data,
+            engine,
+            save_dir,
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Answers requests, one at a time, until the server fails.
+    pub fn run(mut self) -> Result<()> {
+        loop {
+            let mut request = self.http.recv()?;
+            let mut body = String::new();
+            let (status, value) = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => {
+                    let method = request.method().as_str().to_string();
+                    match self.handle(&method, request.url(), &body) {
+                        Ok(value) => (200, value),
+                        Err(Error(status, message)) => (status, json!({ "error": message })),
+                    }
+                }
+                Err(_) => (400, json!({ "error": "the body is not valid UTF-8" })),
+            };
+            let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+            let response = Response::from_string(value.to_string())
+                .with_status_code(status)
+                .with_header(content_type);
+            // The client may have gone away already, which is no reason to stop the server.
+            request.respond(response).ok();
+        }
+    }
+
+    fn handle(&mut self, method: &str, url: &str, body: &str) -> Result<Value, Error> {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let segments: Vec<String> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                percent_decode(segment)
+                    .ok_or_else(|| Error::bad_request(format!("invalid path {path:?}")))
+            })
+            .collect::<Result<_, _>>()?;
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let count = count(query)?;
+
+        match (method, segments.as_slice()) {
+            ("GET", ["users"]) => Ok(self.list_users()),
+            ("GET", ["movies"]) => Ok(json!(self.data.movies)),
+            ("GET", ["users", name, "recs"]) => self.user_recs(name, count, false),
+            ("GET", ["users", name, "anti-recs"]) => self.user_recs(name, count, true),
+            ("GET", ["movies", id, "similar"]) => self.similar(id, count),
+            ("POST", ["users", name, "ratings"]) => self.add_ratings(name, body),
+            _ => Err(Error::not_found(format!(
+                "no such endpoint: {method} {path}"
+            ))),
+        }
+    }
+
+    fn list_users(&self) -> Value {
+        let users: Vec<_> = self
+            .users
+            .iter()
+            .map(|user| json!({ "name": user.name, "ratings": user.ratings.len() }))
+            .collect();
+        json!(users)
+    }
+
+    fn movie(&self, id: &str) -> Option<&ImdbTitle> {
+        Some(&self.data.movies[*self.movie_index.get(id)?])
+    }
+
+    /// Returns a list of movies with a score, such as the predicted rating.
+    fn movie_list(&self, movies: &[(&str, f32)], score: &str) -> Value {
+        let movies: Vec<_> = movies
+            .iter()
+            .map(|&(id, value)| {
+                let title = self.movie(id).map(|movie| movie.primary_title.as_str());
+                let mut entry = json!({ "movie": id, "title": title });
+                entry[score] = json!(value);
+                entry
+            })
+            .collect();
+        json!(movies)
+    }
+
+    fn user_recs(&self, name: &str, count: usize, anti: bool) -> Result<Value, Error> {
+        if !self.engine.has_user(name) {
+            return Err(Error::not_found(format!("unknown user {name:?}")));
+        }
+        let recs = if anti {
+            let mut recs = self.engine.user_recs(name, usize::MAX);
+            recs.reverse();
+            recs.truncate(count);
+            recs
+        } else {
+            self.engine.user_recs(name, count)
+        };
+        Ok(self.movie_list(&recs, "prediction"))
+    }
+
+    fn similar(&self, id: &str, count: usize) -> Result<Value, Error> {
+        if self.movie(id).is_none() {
+            return Err(Error::not_found(format!("unknown movie {id:?}")));
+        }
+        Ok(self.movie_list(&self.engine.item_recs(id, count), "similarity"))
+    }
+
+    /// Adds the ratings to the user, replacing earlier ratings of the same movies,
+    /// or adds a new user if there's none with this name.
+    fn add_ratings(&mut self, name: &str, body: &str) -> Result<Value, Error> {
+        if name.trim().is_empty() {
+            return Err(Error::bad_request("the user name is empty"));
+        }
+        let ratings: Vec<Rating> = serde_json::from_str(body).map_err(|e| {
+            Error::bad_request(format!(
+                "expected a list of {{\"movie\": id, \"rating\": number}}: {e}"
+            ))
+        })?;
+        for rating in &ratings {
+            if self.movie(&rating.movie).is_none() {
+                return Err(Error::bad_request(format!(
+                    "unknown movie {:?}",
+                    rating.movie
+                )));
+            }
+            if !RATING_RANGE.contains(&rating.rating) {
+                return Err(Error::bad_request(format!(
+                    "rating {} of {:?} is outside of the {}-{} scale",
+                    rating.rating,
+                    rating.movie,
+                    RATING_RANGE.start(),
+                    RATING_RANGE.end(),
+                )));
+            }
+        }
+
+        let index = match self.users.iter().position(|user| user.name == name) {
+            Some(index) => index,
+            None => {
+                // Users only used for training keep their ratings, but now show up in the list.
+                let existing = self.data.extra_users.iter().find(|user| user.name == name);
+                self.users.push(User {
+                    name: name.to_string(),
+                    ratings: existing
+                        .map(|user| user.ratings.clone())
+                        .unwrap_or_default(),
+                });
+                self.users.len() - 1
+            }
+        };
+        let user = &mut self.users[index];
+        for rating in ratings {
+            user.ratings.retain(|other| other.movie != rating.movie);
+            user.ratings.push(rating);
+        }
+
+        let engine_ratings: Vec<_> = user
+            .ratings
+            .iter()
+            .map(|rating| (rating.movie.as_str(), rating.rating as f32))
+            .collect();
+        self.engine.add_user(name, &engine_ratings);
+
+        if let Some(dir) = &self.save_dir {
+            self.data
+                .save_user(dir, user)
+                .map_err(|e| Error(500, format!("failed to save the ratings: {e}")))?;
+        }
+        Ok(json!({ "name": user.name, "ratings": user.ratings.len() }))
+    }
+}
+
+/// Reads the `count` parameter from the query string.
+fn count(query: &str) -> Result<usize, Error> {
+    let value = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "count")
+        .map(|(_, value)| value);
+    match value {
+        None => Ok(DEFAULT_COUNT),
+        Some(value) => value
+            .parse()
+            .map_err(|_| Error::bad_request(format!("invalid count {value:?}"))),
+    }
+}
+
+/// Decodes `%XX` escapes, such as `%20` for a space in a user name.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,214 @@
+//! Movie posters, downloaded and decoded in the background, so the interface never waits
+//! for the network and keeps working offline.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, Sender, channel},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::eyre::Result;
+use image::{DynamicImage, ImageReader};
+use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
+use rekomendacje::ImdbTitle;
+use reqwest::Url;
+
+/// How many movies before and after the shown one have their posters loaded in advance.
+pub const PREFETCH: usize = 2;
+/// How many decoded posters are kept in memory.
+const MAX_DECODED: usize = 32;
+/// Once the downloaded posters take more space than this, the least recently used ones are removed.
+const MAX_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+/// Posters are scaled down to fit in this many pixels before they're kept in memory,
+/// which is still more than any terminal can show.
+const MAX_SIZE: u32 = 600;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The state of a movie's poster.
+pub enum Poster<'a> {
+    Loading,
+    Ready(&'a mut StatefulProtocol),
+    /// Couldn't be downloaded or decoded, for example when offline.
+    Unavailable,
+}
+
+enum Entry {
+    Loading,
+    Ready(StatefulProtocol),
+    Unavailable,
+}
+
+/// A poster to download, if it isn't cached yet, and decode.
+struct Job {
+    movie: String,
+    url: String,
+}
+
+pub struct Thumbnails {
+    /// Picks how images are shown in the terminal. Querying the terminal is slow,
+    /// so it's done once.
+    picker: Picker,
+    jobs: Sender<Job>,
+    results: Receiver<(String, Result<DynamicImage>)>,
+    entries: HashMap<String, Entry>,
+    /// Movies with a decoded poster, from the least recently loaded one.
+    decoded: VecDeque<String>,
+}
+
+impl Thumbnails {
+    /// Starts the worker loading posters into `cache`. Has to be called after the terminal
+    /// is set up, since the terminal is asked how it can show images.
+    pub fn new(cache: PathBuf) -> Thumbnails {
+        #[cfg(not(target_os = "windows"))]
+        let picker = Picker::from_query_stdio().unwrap_or_else(|_| Picker::from_fontsize((8, 12)));
+        #[cfg(target_os = "windows")]
+        let picker = Picker::from_fontsize((8, 12));
+
+        let (jobs, job_receiver) = channel::<Job>();
+        let (result_sender, results) = channel();
+        thread::spawn(move || {
+            // Removing old posters only makes the cache smaller, so failing to do it is fine.
+            trim_cache(&cache).ok();
+            for job in job_receiver {
+                let result = load(&cache, &job);
+                if result.is_ok() {
+                    trim_cache(&cache).ok();
+                }
+                if result_sender.send((job.movie, result)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Thumbnails {
+            picker,
+            jobs,
+            results,
+            entries: HashMap::new(),
+            decoded: VecDeque::new(),
+        }
+    }
+
+    /// Starts loading the poster in the background, unless it's loaded already.
+    pub fn request(&mut self, movie: &ImdbTitle) {
+        if self.entries.contains_key(&movie.id) {
+            return;
+        }
+        let entry = match &movie.primary_image {
+            Some(image) => {
+                let job = Job {
+                    movie: movie.id.clone(),
+                    url: image.url.clone(),
+                };
+                match self.jobs.send(job) {
+                    Ok(()) => Entry::Loading,
+                    Err(_) => Entry::Unavailable,
+                }
+            }
+            None => Entry::Unavailable,
+        };
+        self.entries.insert(movie.id.clone(), entry);
+    }
+
+    /// Starts loading the posters of the movies around `index`, from the nearest ones.
+    pub fn prefetch<'a>(&mut self, movies: impl Fn(usize) -> Option<&'a ImdbTitle>, index: usize) {
+        for distance in 1..=PREFETCH {
+            let before = index.checked_sub(distance).and_then(&movies);
+            for movie in [movies(index + distance), before].into_iter().flatten() {
+                self.request(movie);
+            }
+        }
+    }
+
+    /// Takes in the posters loaded by the worker, and returns whether there were any.
+    pub fn update(&mut self) -> bool {
+        let mut updated = false;
+        while let Ok((movie, result)) = self.results.try_recv() {
+            updated = true;
+            let entry = match result {
+                Ok(image) => {
+                    self.decoded.push_back(movie.clone());
+                    Entry::Ready(self.picker.new_resize_protocol(image))
+                }
+                Err(_) => Entry::Unavailable,
+            };
+            self.entries.insert(movie, entry);
+        }
+        while self.decoded.len() > MAX_DECODED {
+            let movie = self.decoded.pop_front().unwrap();
+            self.entries.remove(&movie);
+        }
+        updated
+    }
+
+    /// Returns the poster of the movie, starting to load it if needed.
+    pub fn get(&mut self, movie: &ImdbTitle) -> Poster<'_> {
+        self.request(movie);
+        match self.entries.get_mut(&movie.id) {
+            Some(Entry::Ready(protocol)) => Poster::Ready(protocol),
+            Some(Entry::Loading) => Poster::Loading,
+            Some(Entry::Unavailable) | None => Poster::Unavailable,
+        }
+    }
+}
+
+/// Downloads the poster, unless it's cached already, and decodes it.
+fn load(cache: &Path, job: &Job) -> Result<DynamicImage> {
+    let url = Url::parse(&job.url)?;
+    let ext = url.path().rsplit_once('.').map_or("jpg", |(_, ext)| ext);
+    let mut path = cache.join(&job.movie);
+    path.set_extension(ext);
+
+    if path.is_file() {
+        // The modification time tells which posters were used recently, see [`trim_cache`].
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+            .ok();
+    } else {
+        let bytes = reqwest::blocking::Client::builder()
+            .timeout(TIMEOUT)
+            .build()?
+            .get(url)
+            .send()?
+            .error_for_status()?
+            .bytes()?;
+        fs::create_dir_all(cache)?;
+        fs::write(&path, bytes)?;
+    }
+
+    match ImageReader::open(&path)?.with_guessed_format()?.decode() {
+        Ok(image) => Ok(image.thumbnail(MAX_SIZE, MAX_SIZE)),
+        Err(e) => {
+            // A broken file, for example from an interrupted download, is downloaded again next time.
+            fs::remove_file(&path).ok();
+            Err(e.into())
+        }
+    }
+}
+
+/// Removes the least recently used posters until the cache fits in [`MAX_CACHE_BYTES`].
+fn trim_cache(cache: &Path) -> Result<()> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(cache)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((entry.path(), metadata.modified()?, metadata.len()));
+        }
+    }
+    let mut total: u64 = files.iter().map(|&(_, _, size)| size).sum();
+    files.sort_by_key(|&(_, modified, _)| modified);
+    for (path, _, size) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        fs::remove_file(path)?;
+        total -= size;
+    }
+    Ok(())
+}
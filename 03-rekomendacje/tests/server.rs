@@ -0,0 +1,196 @@
+//! Requests to the HTTP server, trained with the content-based model, which trains quickly.
+
+use std::{fs, net::SocketAddr, path::PathBuf, process, sync::mpsc, thread};
+
+use rekomendacje::{data::Data, model::Hyperparams, recommender::Algorithm, server::Server};
+use reqwest::{StatusCode, blocking::Client};
+use serde_json::{Value, json};
+
+/// Starts a server on a free port, without saving any ratings, and returns its URL
+/// with the data it serves.
+fn start() -> (String, Data) {
+    start_saving(None)
+}
+
+/// Starts a server on a free port, which saves the ratings it gets in `save_dir`.
+fn start_saving(save_dir: Option<PathBuf>) -> (String, Data) {
+    let (sender, receiver) = mpsc::channel::<SocketAddr>();
+    thread::spawn(move || {
+        let data = Data::embedded().unwrap();
+        let engine =
+            Algorithm::Content.train(&data.ratings(), &data.movies, Hyperparams::default());
+        let server = Server::bind("127.0.0.1:0", data, engine, save_dir).unwrap();
+        sender.send(server.addr().unwrap()).unwrap();
+        server.run().unwrap();
+    });
+    let addr = receiver.recv().unwrap();
+    (format!("http://{addr}"), Data::embedded().unwrap())
+}
+
+fn get(url: &str) -> (StatusCode, Value) {
+    let response = reqwest::blocking::get(url).unwrap();
+    (response.status(), response.json().unwrap())
+}
+
+fn post(url: &str, body: &Value) -> (StatusCode, Value) {
+    let response = Client::new().post(url).json(body).send().unwrap();
+    (response.status(), response.json().unwrap())
+}
+
+#[test]
+fn lists_users_and_movies() {
+    let (url, data) = start();
+
+    let (status, users) = get(&format!("{url}/users"));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(users.as_array().unwrap().len(), data.users.len());
+    assert_eq!(users[0]["name"], data.users[0].name);
+    assert_eq!(users[0]["ratings"], data.users[0].ratings.len());
+
+    let (status, movies) = get(&format!("{url}/movies"));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(movies.as_array().unwrap().len(), data.movies.len());
+}
+
+#[test]
+fn recommends_unrated_movies() {
+    let (url, data) = start();
+    let user = &data.users[0];
+
+    let (status, recs) = get(&format!("{url}/users/{}/recs?count=5", user.name));
+    assert_eq!(status, StatusCode::OK);
+    let recs = recs.as_array().unwrap();
+    assert_eq!(recs.len(), 5);
+    for rec in recs {
+        assert!(
+            user.ratings
+                .iter()
+                .all(|rating| rec["movie"] != rating.movie)
+        );
+        assert!(rec["title"].is_string());
+    }
+    let predictions: Vec<f64> = recs
+        .iter()
+        .map(|rec| rec["prediction"].as_f64().unwrap())
+        .collect();
+    assert!(predictions.is_sorted_by(|a, b| a >= b));
+
+    let (status, anti_recs) = get(&format!("{url}/users/{}/anti-recs?count=5", user.name));
+    assert_eq!(status, StatusCode::OK);
+    let worst = anti_recs[0]["prediction"].as_f64().unwrap();
+    assert!(worst <= predictions[4]);
+}
+
+#[test]
+fn lists_similar_movies() {
+    let (url, data) = start();
+    let movie = &data.movies[0].id;
+
+    let (status, similar) = get(&format!("{url}/movies/{movie}/similar?count=3"));
+    assert_eq!(status, StatusCode::OK);
+    let similar = similar.as_array().unwrap();
+    assert_eq!(similar.len(), 3);
+    assert!(similar.iter().all(|other| other["movie"] != *movie));
+    assert!(similar[0]["similarity"].is_number());
+}
+
+#[test]
+fn adds_ratings_of_a_new_user() {
+    let (url, data) = start();
+    let ratings = json!([
+        { "movie": data.movies[0].id, "rating": 9 },
+        { "movie": data.movies[1].id, "rating": 2 },
+    ]);
+
+    let (status, user) = post(&format!("{url}/users/Nowy%20widz/ratings"), &ratings);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(user, json!({ "name": "Nowy widz", "ratings": 2 }));
+
+    let (status, recs) = get(&format!("{url}/users/Nowy%20widz/recs"));
+    assert_eq!(status, StatusCode::OK);
+    let recs = recs.as_array().unwrap();
+    assert!(!recs.is_empty());
+    assert!(recs.iter().all(|rec| rec["movie"] != data.movies[0].id));
+
+    let (_, users) = get(&format!("{url}/users"));
+    assert!(
+        users
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|user| user["name"] == "Nowy widz")
+    );
+}
+
+#[test]
+fn saves_ratings_of_a_training_user() {
+    let dir = std::env::temp_dir().join(format!("rekomendacje-server-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (url, data) = start_saving(Some(dir.clone()));
+    let user = &data.extra_users[0];
+    let movie = data
+        .movies
+        .iter()
+        .find(|movie| user.ratings.iter().all(|rating| rating.movie != movie.id))
+        .unwrap();
+
+    let ratings = json!([{ "movie": movie.id, "rating": 7 }]);
+    let name = user.name.replace(' ', "%20");
+    let (status, saved) = post(&format!("{url}/users/{name}/ratings"), &ratings);
+    assert_eq!(status, StatusCode::OK, "{saved}");
+    assert_eq!(saved["ratings"], user.ratings.len() + 1);
+
+    let saved = Data::load(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        saved
+            .extra_users
+            .iter()
+            .all(|other| other.name != user.name)
+    );
+    let saved_user = saved
+        .users
+        .iter()
+        .find(|other| other.name == user.name)
+        .unwrap();
+    assert_eq!(saved_user.ratings.len(), user.ratings.len() + 1);
+}
+
+#[test]
+fn rejects_invalid_requests() {
+    let (url, data) = start();
+
+    let (status, error) = get(&format!("{url}/users/Nikt/recs"));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(error["error"].is_string());
+
+    let (status, _) = get(&format!("{url}/movies/tt0000000/similar"));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get(&format!("{url}/nothing"));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get(&format!(
+        "{url}/users/{}/recs?count=many",
+        data.users[0].name
+    ));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let ratings = json!([{ "movie": data.movies[0].id, "rating": 11 }]);
+    let (status, _) = post(&format!("{url}/users/Nowy/ratings"), &ratings);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let ratings = json!([{ "movie": "tt0000000", "rating": 5 }]);
+    let (status, _) = post(&format!("{url}/users/Nowy/ratings"), &ratings);
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = post(
+        &format!("{url}/users/Nowy/ratings"),
+        &json!({ "rating": 5 }),
+    );
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Rejected ratings don't add the user.
+    let (status, _) = get(&format!("{url}/users/Nowy/recs"));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
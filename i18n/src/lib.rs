@@ -0,0 +1,230 @@
+#![doc = include_str!("../README.md")]
+
+use std::{collections::HashMap, fmt::Display, str::FromStr, sync::OnceLock};
+
+/// Language the user interface is shown in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    Pl,
+    #[default]
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Pl, Locale::En];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Pl => "pl",
+            Locale::En => "en",
+        }
+    }
+
+    /// Picks the locale from the standard environment variables, in the order
+    /// they take precedence in: `LC_ALL`, `LC_MESSAGES`, then `LANG`.
+    ///
+    /// Anything other than Polish falls back to English.
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_posix(&value))
+            .unwrap_or_default()
+    }
+
+    /// Parses a POSIX locale name, such as `pl_PL.UTF-8`.
+    fn from_posix(value: &str) -> Option<Self> {
+        let language = value.split(['_', '.', '@', '-']).next()?;
+        Some(language.parse().unwrap_or_default())
+    }
+
+    /// Removes a `--lang <code>` flag from the command line arguments, and returns the locale
+    /// it picks. Without the flag, the locale is [detected](Locale::detect) from the environment.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, UnknownLocale> {
+        let Some(index) = args.iter().position(|arg| arg == "--lang") else {
+            return Ok(Self::detect());
+        };
+        let code = args.get(index + 1).cloned().unwrap_or_default();
+        args.drain(index..(index + 2).min(args.len()));
+        code.parse()
+    }
+
+    /// Returns the plural form used for `n` items.
+    fn plural(self, n: u64) -> &'static str {
+        match self {
+            Locale::En if n == 1 => "one",
+            Locale::En => "many",
+            Locale::Pl if n == 1 => "one",
+            // 2-4, 22-24, 32-34..., but not 12-14.
+            Locale::Pl if matches!(n % 10, 2..=4) && !matches!(n % 100, 12..=14) => "few",
+            Locale::Pl => "many",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLocale(pub String);
+
+impl Display for UnknownLocale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let known: Vec<_> = Locale::ALL.iter().map(|l| l.code()).collect();
+        write!(
+            f,
+            "unknown language {:?}, expected one of: {}",
+            self.0,
+            known.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownLocale {}
+
+impl FromStr for Locale {
+    type Err = UnknownLocale;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pl" => Ok(Locale::Pl),
+            "en" => Ok(Locale::En),
+            _ => Err(UnknownLocale(s.to_string())),
+        }
+    }
+}
+
+/// Message catalogs of a program, one for each [`Locale`], usually embedded with `include_str!`.
+///
+/// Every line of a catalog has the form `key = message`. Empty lines and lines starting
+/// with `#` are ignored. Messages can refer to arguments as `{name}`, see [`tr!`].
+#[derive(Debug, Clone, Copy)]
+pub struct Catalogs {
+    pub pl: &'static str,
+    pub en: &'static str,
+}
+
+struct Messages {
+    locale: Locale,
+    selected: HashMap<&'static str, &'static str>,
+    fallback: HashMap<&'static str, &'static str>,
+}
+
+static MESSAGES: OnceLock<Messages> = OnceLock::new();
+
+fn parse(catalog: &'static str) -> HashMap<&'static str, &'static str> {
+    catalog
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, message)| (key.trim(), message.trim()))
+        .collect()
+}
+
+/// Selects the messages to use for the rest of the program.
+///
+/// Only the first call has any effect, later ones are ignored.
+pub fn init(catalogs: Catalogs, locale: Locale) {
+    MESSAGES.get_or_init(|| Messages {
+        locale,
+        selected: parse(match locale {
+            Locale::Pl => catalogs.pl,
+            Locale::En => catalogs.en,
+        }),
+        fallback: parse(catalogs.en),
+    });
+}
+
+/// Returns the locale picked with [`init`].
+pub fn locale() -> Locale {
+    MESSAGES.get().map(|m| m.locale).unwrap_or_default()
+}
+
+/// Returns the message with the given key.
+///
+/// Messages missing from the selected catalog are taken from the English one.
+/// If the key is missing there too, or [`init`] wasn't called, the key itself is returned,
+/// so a missing translation is easy to spot, but doesn't break anything.
+pub fn t(key: &'static str) -> &'static str {
+    let Some(messages) = MESSAGES.get() else {
+        return key;
+    };
+    messages
+        .selected
+        .get(key)
+        .or_else(|| messages.fallback.get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+/// Returns the message with the given key in the plural form for `n` items.
+///
+/// The forms are stored under `key.one`, `key.few` (only used in Polish) and `key.many`.
+/// If none of them is found, the key itself is returned, like in [`t`].
+pub fn t_plural(key: &'static str, n: u64) -> &'static str {
+    let Some(messages) = MESSAGES.get() else {
+        return key;
+    };
+    let full = format!("{key}.{}", messages.locale.plural(n));
+    messages
+        .selected
+        .get(full.as_str())
+        .or_else(|| messages.fallback.get(full.as_str()))
+        .copied()
+        .unwrap_or(key)
+}
+
+/// Replaces the `{name}` placeholders in a message with the given arguments.
+///
+/// Placeholders without a matching argument are left as they are.
+pub fn format(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let arg = rest.find('}').and_then(|end| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &rest[1..end])?;
+            Some((end, value))
+        });
+        match arg {
+            Some((end, value)) => {
+                result.push_str(&value.to_string());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Translates a message and fills in its arguments.
+///
+/// With a count after a semicolon, picks the right [plural form](t_plural) of the message,
+/// and passes the count as the `{n}` argument.
+///
+/// ```
+/// # use i18n::tr;
+/// let position = 3;
+/// let line = tr!("replay.move", position = position, total = 40, m = "c3-d4");
+/// let start = tr!("replay.start"; 40);
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($key:expr; $n:expr $(, $name:ident = $value:expr)* $(,)?) => {{
+        let n = $n;
+        $crate::format(
+            $crate::t_plural($key, n as u64),
+            &[("n", &n as &dyn ::std::fmt::Display) $(, (stringify!($name), &$value as &dyn ::std::fmt::Display))*],
+        )
+    }};
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::format(
+            $crate::t($key),
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
@@ -41,13 +41,20 @@ rustPlatform.buildRustPackage {
         ../Cargo.toml
         ../Cargo.lock
 
+        ../i18n/src
+        ../i18n/Cargo.toml
+        ../i18n/README.md
+
         ../01-warcaby/src
         ../01-warcaby/Cargo.toml
         ../01-warcaby/README.md
+        ../01-warcaby/puzzles.txt
+        ../01-warcaby/locales
 
         ../03-rekomendacje/src
         ../03-rekomendacje/Cargo.toml
         ../03-rekomendacje/README.md
+        ../03-rekomendacje/locales
 
         ../04-klasyfikacja/src
         ../04-klasyfikacja/Cargo.toml